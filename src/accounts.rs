@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::fs;
+
+// Names of users or groups by their numeric id, read from /etc/passwd or /etc/group. Both files
+// have the name in the first field and the id in the third one.
+#[derive(Debug)]
+pub struct Accounts {
+    names: HashMap<u32, String>,
+    ids: HashMap<String, u32>,
+}
+
+impl Accounts {
+    pub fn users() -> Accounts {
+        Accounts::from_file("/etc/passwd")
+    }
+
+    pub fn groups() -> Accounts {
+        Accounts::from_file("/etc/group")
+    }
+
+    // A missing file is no error; every id is shown as a number then.
+    fn from_file(path: &str) -> Accounts {
+        Accounts::parse(&fs::read_to_string(path).unwrap_or_default())
+    }
+
+    pub fn parse(contents: &str) -> Accounts {
+        let mut names = HashMap::new();
+        let mut ids = HashMap::new();
+
+        for line in contents.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 3 || fields[0].is_empty() {
+                continue;
+            }
+            if let Ok(id) = fields[2].parse::<u32>() {
+                // The first entry wins when an id is listed more than once, like getpwuid(3).
+                names.entry(id).or_insert(fields[0].to_string());
+                ids.entry(fields[0].to_string()).or_insert(id);
+            }
+        }
+
+        Accounts { names: names, ids: ids }
+    }
+
+    pub fn name(&self, id: u32) -> String {
+        match self.names.get(&id) {
+            Some(name) => name.clone(),
+            None => id.to_string(),
+        }
+    }
+
+    // Finds the id of a name, which can also be given as a number.
+    pub fn id(&self, name: &str) -> Result<u32, String> {
+        self.ids.get(name).cloned()
+            .or_else(|| name.parse::<u32>().ok())
+            .ok_or_else(|| format!("Unknown name \"{}\"", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &'static str = "\
+# Comments are ignored
+root:x:0:0:root:/root:/bin/bash
+alice:x:1000:1000:Alice:/home/alice:/bin/zsh
+broken line
+toor:x:0:0:another root:/root:/bin/sh
+";
+
+    #[test]
+    fn it_resolves_names_by_id() {
+        let accounts = Accounts::parse(PASSWD);
+
+        assert_eq!(accounts.name(0), "root");
+        assert_eq!(accounts.name(1000), "alice");
+        assert_eq!(accounts.name(1234), "1234");
+    }
+
+    #[test]
+    fn it_resolves_ids_by_name_or_number() {
+        let accounts = Accounts::parse(PASSWD);
+
+        assert_eq!(accounts.id("alice"), Ok(1000));
+        assert_eq!(accounts.id("toor"), Ok(0));
+        assert_eq!(accounts.id("1234"), Ok(1234));
+        assert_eq!(accounts.id("bob"), Err("Unknown name \"bob\"".to_string()));
+    }
+}
@@ -0,0 +1,512 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use flate2::bufread::GzDecoder;
+
+const TAR_BLOCK: u64 = 512;
+// Long names are read into memory, so headers with them are refused above this size.
+const TAR_MAX_EXTENDED_HEADER: u64 = 1024 * 1024;
+const ZIP_END: u32 = 0x06054b50;
+const ZIP64_END: u32 = 0x06064b50;
+const ZIP64_LOCATOR: u32 = 0x07064b50;
+const ZIP_DIRECTORY_ENTRY: u32 = 0x02014b50;
+// The end of central directory record is 22 bytes, followed by a comment of at most 64 KiB.
+const ZIP_MAX_END: u64 = 22 + 0xffff;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Format {
+    Tar,
+    GzippedTar,
+    Zip,
+}
+
+// A file in an archive. The compressed size is how much of the archive it takes up, which for
+// gzipped tar files can only be told roughly since they are compressed as a whole.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Member {
+    pub path: String,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+// The members of an archive by the directories they are in.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Directory {
+    pub files: Vec<Member>,
+    pub directories: BTreeMap<String, Directory>,
+}
+
+impl Directory {
+    fn add(&mut self, parts: &[&str], member: Member) {
+        match parts.split_first() {
+            Some((name, rest)) if !rest.is_empty() => {
+                self.directories.entry(name.to_string())
+                    .or_insert_with(Directory::default)
+                    .add(rest, member)
+            },
+            _ => self.files.push(member),
+        }
+    }
+}
+
+// Tells archives apart by their names, so files that are not archives are never opened.
+pub fn is_archive(path: &Path) -> bool {
+    format(path).is_some()
+}
+
+fn format(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".tar") {
+        Some(Format::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Format::GzippedTar)
+    } else if name.ends_with(".zip") || name.ends_with(".jar") {
+        Some(Format::Zip)
+    } else {
+        None
+    }
+}
+
+// Reads what is in the archive at the path. Members are put in directories by their paths in the
+// archive; what only exists as a directory in the archive is left out.
+pub fn read<R: Read + Seek>(path: &Path, contents: R) -> io::Result<Directory> {
+    let members = match format(path) {
+        Some(Format::Tar) => {
+            read_tar(Counter::new(BufReader::new(contents)), |counter| counter.count)?
+        },
+        Some(Format::GzippedTar) => {
+            let decoder = GzDecoder::new(Counter::new(BufReader::new(contents)));
+            read_tar(decoder, |decoder| decoder.get_ref().count)?
+        },
+        Some(Format::Zip) => read_zip(contents)?,
+        None => return Err(invalid("not an archive")),
+    };
+
+    let mut root = Directory::default();
+    for member in members {
+        let path = member.path.clone();
+        let parts: Vec<&str> = path.split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        if !parts.is_empty() {
+            root.add(&parts, member);
+        }
+    }
+    Ok(root)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Keeps count of how much has been read, so it can be told how much of a compressed archive a
+// member took up. Decompressing only consumes what it used of what it was given to read.
+struct Counter<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R> Counter<R> {
+    fn new(inner: R) -> Counter<R> {
+        Counter { inner: inner, count: 0 }
+    }
+}
+
+impl<R: BufRead> Read for Counter<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Counter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.count += amount as u64;
+    }
+}
+
+// Reads the headers of a tar file and skips over the data in between. Long names are read from
+// GNU and pax extended headers, and only regular files are kept.
+fn read_tar<R, F>(mut reader: R, position: F) -> io::Result<Vec<Member>>
+    where R: Read,
+          F: Fn(&R) -> u64 {
+    let mut members = Vec::new();
+    let mut long_name = None;
+    let mut header = [0; TAR_BLOCK as usize];
+
+    loop {
+        let start = position(&reader);
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+
+        let size = tar_number(&header[124..136])?;
+        let padded = size.checked_add(TAR_BLOCK - 1)
+            .ok_or_else(|| invalid("tar member too large"))? / TAR_BLOCK * TAR_BLOCK;
+        match header[156] {
+            b'L' | b'x' => {
+                if size > TAR_MAX_EXTENDED_HEADER {
+                    return Err(invalid("tar extended header too large"));
+                }
+                let mut data = Vec::new();
+                (&mut reader).take(padded).read_to_end(&mut data)?;
+                data.truncate(size as usize);
+                long_name = if header[156] == b'L' {
+                    Some(text(&data))
+                } else {
+                    pax_path(&data).or(long_name)
+                };
+            },
+            kind => {
+                skip(&mut reader, padded)?;
+                let path = long_name.take().unwrap_or_else(|| tar_path(&header));
+                if kind == b'0' || kind == 0 || kind == b'7' {
+                    let compressed_size = position(&reader) - start;
+                    members.push(Member {
+                        path: path,
+                        size: size,
+                        compressed_size: compressed_size,
+                    });
+                }
+            },
+        }
+    }
+
+    Ok(members)
+}
+
+// Archives are allowed to end without the blocks of zeros that should end them.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < block.len() {
+        match reader.read(&mut block[read..])? {
+            0 if read == 0 => return Ok(false),
+            0 => return Err(invalid("tar block cut short")),
+            count => read += count,
+        }
+    }
+    Ok(true)
+}
+
+fn skip<R: Read>(reader: &mut R, length: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(length), &mut io::sink())? < length {
+        Err(invalid("tar member cut short"))
+    } else {
+        Ok(())
+    }
+}
+
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+// Names in ustar headers can be split in a prefix and a name.
+fn tar_path(header: &[u8]) -> String {
+    let name = text(&header[0..100]);
+    let prefix = text(&header[345..500]);
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        format!("{}/{}", prefix, name)
+    } else {
+        name
+    }
+}
+
+// Numbers are octal text, or big-endian binary when the first bit is set.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let value = field[1..].iter().fold(0, |value: u64, &byte| (value << 8) | byte as u64);
+        return Ok(value);
+    }
+
+    let digits = text(field);
+    let digits = digits.trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        Ok(0)
+    } else {
+        u64::from_str_radix(digits, 8).map_err(|_| invalid("invalid number in tar header"))
+    }
+}
+
+// Pax headers are records like "30 path=some/very/long/name\n".
+fn pax_path(data: &[u8]) -> Option<String> {
+    let data = String::from_utf8_lossy(data);
+    data.lines()
+        .filter_map(|record| record.splitn(2, ' ').nth(1))
+        .filter_map(|field| {
+            let mut parts = field.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some("path"), Some(path)) => Some(path.to_string()),
+                _ => None,
+            }
+        })
+        .last()
+}
+
+// Zip files list their members in a central directory at the end, so only that has to be read.
+fn read_zip<R: Read + Seek>(mut reader: R) -> io::Result<Vec<Member>> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    let tail_length = cmp::min(length, ZIP_MAX_END);
+    reader.seek(SeekFrom::Start(length - tail_length))?;
+    let mut tail = Vec::new();
+    (&mut reader).take(tail_length).read_to_end(&mut tail)?;
+
+    let end = (0..tail.len().saturating_sub(21)).rev()
+        .find(|&index| le32(&tail[index..]) == ZIP_END)
+        .ok_or_else(|| invalid("no end of central directory"))?;
+
+    let mut count = le16(&tail[end + 10..]) as u64;
+    let mut offset = le32(&tail[end + 16..]) as u64;
+    if (count == 0xffff || offset == 0xffff_ffff) && end >= 20 &&
+       le32(&tail[end - 20..]) == ZIP64_LOCATOR {
+        reader.seek(SeekFrom::Start(le64(&tail[end - 12..])))?;
+        let mut record = [0; 56];
+        reader.read_exact(&mut record)?;
+        if le32(&record) != ZIP64_END {
+            return Err(invalid("no zip64 end of central directory"));
+        }
+        count = le64(&record[32..]);
+        offset = le64(&record[48..]);
+    }
+
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut reader = io::BufReader::new(reader);
+    let mut members = Vec::new();
+    for _ in 0..count {
+        let mut header = [0; 46];
+        reader.read_exact(&mut header)?;
+        if le32(&header) != ZIP_DIRECTORY_ENTRY {
+            return Err(invalid("broken central directory"));
+        }
+
+        let mut name = vec![0; le16(&header[28..]) as usize];
+        reader.read_exact(&mut name)?;
+        let mut extra = vec![0; le16(&header[30..]) as usize];
+        reader.read_exact(&mut extra)?;
+        skip(&mut reader, le16(&header[32..]) as u64)?;
+
+        let path = String::from_utf8_lossy(&name).into_owned();
+        if path.ends_with('/') {
+            continue;
+        }
+
+        let (size, compressed_size) = zip64_sizes(le32(&header[24..]), le32(&header[20..]), &extra);
+        members.push(Member { path: path, size: size, compressed_size: compressed_size });
+    }
+
+    Ok(members)
+}
+
+// Sizes that do not fit in 32 bits are in the zip64 extra field instead, uncompressed first.
+fn zip64_sizes(size: u32, compressed_size: u32, extra: &[u8]) -> (u64, u64) {
+    let mut sizes = (size as u64, compressed_size as u64);
+    let mut index = 0;
+    while index + 4 <= extra.len() {
+        let id = le16(&extra[index..]);
+        let length = le16(&extra[index + 2..]) as usize;
+        let end = cmp::min(index + 4 + length, extra.len());
+        if id == 1 {
+            let mut values = extra[index + 4..end].chunks(8).filter(|value| value.len() == 8);
+            if size == 0xffff_ffff {
+                sizes.0 = values.next().map(le64).unwrap_or(sizes.0);
+            }
+            if compressed_size == 0xffff_ffff {
+                sizes.1 = values.next().map(le64).unwrap_or(sizes.1);
+            }
+        }
+        index = end;
+    }
+    sizes
+}
+
+fn le16(bytes: &[u8]) -> u16 {
+    bytes[0] as u16 | (bytes[1] as u16) << 8
+}
+
+fn le32(bytes: &[u8]) -> u32 {
+    le16(bytes) as u32 | (le16(&bytes[2..]) as u32) << 16
+}
+
+fn le64(bytes: &[u8]) -> u64 {
+    le32(bytes) as u64 | (le32(&bytes[4..]) as u64) << 32
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use std::io::{Cursor, Write};
+
+    // Makes a tar file with the files in it, padded like tar does.
+    pub fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for &(name, data) in files {
+            let mut header = [0; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            archive.extend_from_slice(&header);
+            archive.extend_from_slice(data);
+            let padding = (512 - data.len() % 512) % 512;
+            archive.extend(vec![0; padding]);
+        }
+        archive.extend(vec![0; 1024]);
+        archive
+    }
+
+    pub fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // Makes a zip file with the files in it deflated. Only what is read is filled in; the local
+    // headers are left empty.
+    pub fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for &(name, data) in files {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            let compressed = encoder.finish().unwrap();
+
+            let offset = archive.len() as u32;
+            archive.extend(vec![0; 30 + name.len()]);
+            archive.extend_from_slice(&compressed);
+
+            let mut header = vec![0; 46];
+            header[0..4].copy_from_slice(&[0x50, 0x4b, 0x01, 0x02]);
+            put32(&mut header[20..], compressed.len() as u32);
+            put32(&mut header[24..], data.len() as u32);
+            header[28] = name.len() as u8;
+            put32(&mut header[42..], offset);
+            directory.extend(header);
+            directory.extend_from_slice(name.as_bytes());
+        }
+
+        let mut end = vec![0; 22];
+        end[0..4].copy_from_slice(&[0x50, 0x4b, 0x05, 0x06]);
+        end[10] = files.len() as u8;
+        put32(&mut end[12..], directory.len() as u32);
+        put32(&mut end[16..], archive.len() as u32);
+        archive.extend(directory);
+        archive.extend(end);
+        archive
+    }
+
+    fn put32(bytes: &mut [u8], value: u32) {
+        for index in 0..4 {
+            bytes[index] = (value >> (8 * index)) as u8;
+        }
+    }
+
+    fn sizes(directory: &Directory) -> Vec<(String, u64, u64)> {
+        directory.files.iter()
+            .map(|member| (member.path.clone(), member.size, member.compressed_size))
+            .collect()
+    }
+
+    #[test]
+    fn it_knows_archives_by_their_names() {
+        assert!(is_archive(Path::new("backup.tar")));
+        assert!(is_archive(Path::new("dir/backup.TAR.GZ")));
+        assert!(is_archive(Path::new("backup.tgz")));
+        assert!(is_archive(Path::new("app.jar")));
+        assert!(is_archive(Path::new("photos.zip")));
+        assert!(!is_archive(Path::new("notes.txt")));
+        assert!(!is_archive(Path::new("tar")));
+    }
+
+    #[test]
+    fn it_reads_tar_files() {
+        let data = tar(&[("docs/a.txt", &[b'a'; 600]), ("b", b"b"), ("docs/deep/c", b"")]);
+        let root = read(Path::new("x.tar"), Cursor::new(data)).unwrap();
+
+        assert_eq!(sizes(&root), vec![("b".to_string(), 1, 1024)]);
+        let docs = &root.directories["docs"];
+        assert_eq!(sizes(docs), vec![("docs/a.txt".to_string(), 600, 1536)]);
+        assert_eq!(sizes(&docs.directories["deep"]), vec![("docs/deep/c".to_string(), 0, 512)]);
+    }
+
+    #[test]
+    fn it_reads_gzipped_tar_files() {
+        let data = gzip(&tar(&[("big", &[b'x'; 100000]), ("small", b"y")]));
+        let length = data.len() as u64;
+        let root = read(Path::new("x.tar.gz"), Cursor::new(data)).unwrap();
+
+        let names: Vec<&String> = root.files.iter().map(|member| &member.path).collect();
+        assert_eq!(names, vec!["big", "small"]);
+        assert_eq!(root.files[0].size, 100000);
+        // Everything is compressed together, so the members take up at most the whole archive.
+        let compressed: u64 = root.files.iter().map(|member| member.compressed_size).sum();
+        assert!(compressed <= length);
+    }
+
+    #[test]
+    fn it_reads_long_tar_names() {
+        let long_name = format!("{}/file", "directory".repeat(20));
+        let mut data = tar(&[("././@LongLink", long_name.as_bytes())]);
+        data.truncate(1024);
+        data[156] = b'L';
+        data.extend(tar(&[("short", b"data")]));
+
+        let root = read(Path::new("x.tar"), Cursor::new(data)).unwrap();
+        assert_eq!(root.directories[&"directory".repeat(20)].files[0].path, long_name);
+
+        assert_eq!(pax_path(b"27 mtime=1243.435332343\n15 path=a/b/c\n"),
+                   Some("a/b/c".to_string()));
+        assert_eq!(tar_number(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]).unwrap(), 256);
+    }
+
+    #[test]
+    fn it_refuses_tar_sizes_that_are_too_large() {
+        let mut data = tar(&[("huge", b"")]);
+        data[124..128].copy_from_slice(&[0x80, 0, 0, 0]);
+        data[128..136].copy_from_slice(&[0xff; 8]);
+        let error = read(Path::new("x.tar"), Cursor::new(data)).unwrap_err();
+        assert_eq!(error.to_string(), "tar member too large");
+
+        let mut data = tar(&[("././@LongLink", b"")]);
+        data[124..135].copy_from_slice(format!("{:011o}", 1u64 << 32).as_bytes());
+        data[156] = b'L';
+        let error = read(Path::new("x.tar"), Cursor::new(data)).unwrap_err();
+        assert_eq!(error.to_string(), "tar extended header too large");
+    }
+
+    #[test]
+    fn it_reads_zip_files() {
+        let data = zip(&[("lib/a.class", &[b'a'; 5000]), ("META-INF/", b""), ("b", b"b")]);
+        let root = read(Path::new("x.jar"), Cursor::new(data)).unwrap();
+
+        assert_eq!(root.files.len(), 1);
+        assert_eq!(root.files[0].size, 1);
+        let class = &root.directories["lib"].files[0];
+        assert_eq!(class.size, 5000);
+        assert!(class.compressed_size < 100);
+        assert!(!root.directories.contains_key("META-INF"));
+    }
+
+    #[test]
+    fn it_reads_large_sizes_in_zip64_fields() {
+        let extra = [1, 0, 16, 0, 0, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(zip64_sizes(0xffff_ffff, 0xffff_ffff, &extra), (1 << 32, 5));
+        assert_eq!(zip64_sizes(10, 5, &[]), (10, 5));
+    }
+
+    #[test]
+    fn it_refuses_broken_archives() {
+        assert!(read(Path::new("x.zip"), Cursor::new(b"not a zip file".to_vec())).is_err());
+        assert!(read(Path::new("x.tar"), Cursor::new(vec![b'1'; 700])).is_err());
+        assert!(read(Path::new("x.tgz"), Cursor::new(vec![1; 700])).is_err());
+    }
+}
@@ -2,108 +2,33 @@ use std::env;
 use std::ffi::OsStr;
 use std::path::PathBuf;
 use std::process::exit;
-use std::str::FromStr;
+use std::time::Duration;
 
-use modes::Mode;
+use clap::ClapError;
 
-#[derive(Debug, PartialEq, Eq)]
-enum Depth {
-    Unlimited,
-    Limited(usize)
-}
-
-impl Depth {
-    fn accepts(&self, level: usize) -> bool {
-        match *self {
-            Depth::Unlimited => true,
-            Depth::Limited(size) => size > level
-        }
-    }
-}
-
-impl FromStr for Depth {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, String> {
-        if s == "all" { return Ok(Depth::Unlimited); }
-
-        let number = match s.parse::<usize>() {
-            Ok(number) => number,
-            Err(_) => return Err("Not a positive integer or \"all\"".to_string())
-        };
-
-        if number > 0 {
-            Ok(Depth::Limited(number))
-        } else {
-            Ok(Depth::Unlimited)
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq)]
-enum Limit {
-    Unlimited,
-    Limited(usize)
-}
-
-impl FromStr for Limit {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, String> {
-        if s == "all" { return Ok(Limit::Unlimited); }
-
-        let number = match s.parse::<usize>() {
-            Ok(number) => number,
-            Err(_) => return Err("Not a positive integer or \"all\"".to_string())
-        };
-
-        if number > 0 {
-            Ok(Limit::Limited(number))
-        } else {
-            Ok(Limit::Unlimited)
-        }
-    }
-}
-
-pub struct Options {
-    roots: Vec<String>,
-    limit: Limit,
-    depth: Depth,
-    mode: Mode,
-    show_all: bool,
-}
-
-impl Options {
-    pub fn roots(&self) -> Vec<PathBuf> {
-        self.roots.iter().map(|root| PathBuf::from(&root)).collect()
-    }
-
-    pub fn mode(&self) -> &Mode {
-        &self.mode
-    }
-
-    pub fn depth_accepts(&self, level: usize) -> bool {
-        self.depth.accepts(level)
-    }
-
-    pub fn limit_reached(&self, shown_entries: usize) -> bool {
-        match self.limit {
-            Limit::Unlimited => false,
-            Limit::Limited(max) => max <= shown_entries
-        }
-    }
-
-    pub fn should_show_hidden(&self) -> bool {
-        self.show_all
-    }
-}
+use dutop::accounts::Accounts;
+use dutop::breakdown::AgeBuckets;
+use dutop::cleanable;
+use dutop::colors::{ColorChoice, Palette};
+use dutop::modes::TreeStyle;
+use dutop::svg::{Coloring, Layout};
+use dutop::terminal;
+use dutop::utils::{self, DigitGrouping, SizeFormat, Units};
+use dutop::{Depth, Format, Limit, Mode, Options, Timestamp};
 
 pub fn parse() -> Options {
     parse_from(env::args())
 }
 
 // Provided for tests
-fn parse_from<I, T>(iterator: I) -> Options
+pub fn parse_from<I, T>(iterator: I) -> Options
+    where I: IntoIterator<Item = T>,
+          T: AsRef<OsStr> {
+    try_parse_from(iterator).unwrap_or_else(|error| error.exit())
+}
+
+// Like parse_from, but hands back the error for invalid arguments instead of exiting.
+pub fn try_parse_from<I, T>(iterator: I) -> Result<Options, ClapError>
     where I: IntoIterator<Item = T>,
           T: AsRef<OsStr> {
     let matches = clap_app!(dutop =>
@@ -140,8 +65,8 @@ fn parse_from<I, T>(iterator: I) -> Options
                     Err("Depth must be a non-negative integer or \"all\".".to_string())
                 }
             }}
-            "The depth to recurse when printing out entries. Defaults to 1. 0 or \"all\" means \
-                unlimited depth."
+            "The depth to recurse when printing out entries. Defaults to 1, or unlimited depth \
+                with --format html. 0 or \"all\" means unlimited depth."
         )
 
         (@arg recursive:
@@ -160,28 +85,365 @@ fn parse_from<I, T>(iterator: I) -> Options
             "Print the largest files instead of a tree. Depth will say how far down to look for \
                 the \"largest\" file."
         )
-    ).get_matches_from(iterator);
+
+        (@arg dirs:
+            --dirs
+            conflicts_with[files]
+            "Print the directories with the most content instead of a tree. Directories are \
+                ranked by the size of the files directly inside of them and shown with their \
+                path relative to the root."
+        )
+
+        (@arg cumulative:
+            --cumulative
+            requires[dirs]
+            "Rank directories by their total size, including subdirectories, in --dirs mode."
+        )
+
+        // The modes that group files, which --per-child applies to.
+        (@group breakdown =>
+            (@arg by_extension:
+                long("by-extension")
+                conflicts_with[files dirs]
+                "Print how much room every file extension takes up, and in how many files, \
+                    instead of a tree. Compressed files keep their inner extension, like \
+                    \".tar.gz\"."
+            )
+
+            (@arg by_owner:
+                long("by-owner")
+                conflicts_with[files dirs by_extension]
+                "Print how much room the files of every user take up instead of a tree."
+            )
+
+            (@arg by_group:
+                long("by-group")
+                conflicts_with[files dirs by_extension by_owner]
+                "Print how much room the files of every group take up instead of a tree."
+            )
+        )
+
+        (@arg by_age:
+            long("by-age")
+            conflicts_with[files dirs by_extension by_owner by_group]
+            "Print a tree where the size of every directory is broken down by how long ago its \
+                files were modified."
+        )
+
+        (@arg by_git:
+            long("by-git")
+            conflicts_with[files dirs by_extension by_owner by_group by_age]
+            "Print a tree where the size of every directory is broken down by what its files are \
+                to the git work trees they are in: tracked, untracked, ignored or part of .git \
+                itself. Only the index and the ignore files of the repositories are read."
+        )
+
+        (@arg duplicates:
+            --duplicates
+            conflicts_with[files dirs by_extension by_owner by_group by_age by_git]
+            "Print the sets of files with the same contents instead of a tree, ordered by how \
+                much room the extra copies waste. Hard links are not counted as copies."
+        )
+
+        (@arg empty:
+            --empty
+            conflicts_with[files dirs by_extension by_owner by_group by_age by_git duplicates
+                owner older_than newer_than]
+            "Print the directories without any files in them and the zero-byte files instead of \
+                a tree, below the directories they are in. Directories that only have empty \
+                directories in them are listed by themselves, and directories that cannot be \
+                read are left out. Everything is listed regardless of -n."
+        )
+
+        (@arg cleanable:
+            --cleanable
+            conflicts_with[files dirs by_extension by_owner by_group by_age by_git duplicates empty]
+            "Print the directories that tools make and can make again, like \"target\" next to \
+                \"Cargo.toml\", \"node_modules\" and \"__pycache__\", instead of a tree, \
+                followed by how much room removing them would free. Directories in them are not \
+                listed again, and everything is listed regardless of -n and -a."
+        )
+
+        (@arg cleanable_rules:
+            long("cleanable-rules") [RULES]
+            requires[cleanable]
+            {|value| cleanable::parse_rules(&value).map(|_| ())}
+            "Also list the directories matching RULES in --cleanable mode. Rules are separated by \
+                commas and are a directory name, optionally followed by a colon and the files \
+                of which one has to be next to it, separated by bars, like \
+                \"dist:package.json,vendor:composer.json|go.mod,.venv\"."
+        )
+
+        (@arg null:
+            --null
+            requires[empty]
+            "Print only the paths in --empty mode, each followed by a NUL character, for use \
+                with \"xargs -0\"."
+        )
+
+        (@arg age_buckets:
+            long("age-buckets") [DAYS]
+            {|value| utils::parse_age_buckets(&value).map(|_| ())}
+            "The limits of the age buckets in --by-age mode, as increasing numbers of days \
+                separated by commas. Defaults to \"30,90,365\"."
+        )
+
+        (@arg atime:
+            --atime
+            "Use the time files were last accessed instead of modified for ages."
+        )
+
+        (@arg older_than:
+            long("older-than") [AGE]
+            {|value| utils::parse_age(&value).map(|_| ())}
+            "Only count files that are at least AGE old. AGE is a number of days, or a number \
+                followed by \"s\", \"m\", \"h\", \"d\", \"w\" or \"y\"."
+        )
+
+        (@arg newer_than:
+            long("newer-than") [AGE]
+            {|value| utils::parse_age(&value).map(|_| ())}
+            "Only count files that are less than AGE old."
+        )
+
+        (@arg per_child:
+            long("per-child")
+            requires[breakdown]
+            "Group the files of every top-level child of the root on its own instead of the \
+                whole root, in --by-extension, --by-owner and --by-group mode."
+        )
+
+        (@arg owner:
+            --owner [USER]
+            "Only count the files owned by USER, given as a name or a numeric id."
+        )
+
+        (@arg min_size:
+            long("min-size") [SIZE]
+            {|value| utils::parse_size(&value).map(|_| ())}
+            "Hide entries that are smaller than SIZE. SIZE is written like for --block-size."
+        )
+
+        (@arg absolute:
+            --absolute
+            "Show absolute paths in --files and --dirs mode. By default paths are relative to \
+                the root."
+        )
+
+        (@arg interactive:
+            -i --interactive
+            conflicts_with[files dirs by_extension by_owner by_group by_age by_git duplicates empty
+                cleanable diff format]
+            "Browse the tree interactively in the terminal. Use the arrow keys to move around \
+                and to expand and collapse directories, \"s\" to change the sort order, \"r\" \
+                to rescan the selected entry and \"q\" to quit."
+        )
+
+        (@arg allow_delete:
+            long("allow-delete")
+            requires[interactive]
+            "Allow deleting the selected entry with \"d\" in interactive mode. Every deletion \
+                has to be confirmed."
+        )
+
+        (@arg save:
+            --save [FILE]
+            "Save the scan to FILE as a snapshot, so it can be looked at again with --load."
+        )
+
+        (@arg load:
+            --load [FILE]
+            conflicts_with[DIR interactive duplicates by_git]
+            "Use the snapshot in FILE instead of scanning. Everything else works just like on \
+                the scan that was saved, except for what reads the files themselves: \
+                --interactive, --duplicates and --by-git."
+        )
+
+        (@arg cache:
+            --cache [FILE]
+            conflicts_with[load]
+            "Keep the scan in FILE, so the next scan with the same FILE does not read the \
+                directories that have not changed since. Files are still looked at, since they \
+                can grow without changing their directory."
+        )
+
+        (@arg into_archives:
+            long("into-archives")
+            conflicts_with[load]
+            "Look into .tar, .tar.gz, .tgz, .zip and .jar files like directories. What is in them \
+                is shown unpacked, next to how much of the archive it takes up, while the archive \
+                itself still counts with its size on disk."
+        )
+
+        (@arg diff:
+            --diff [SNAPSHOT]
+            conflicts_with[files dirs by_extension by_owner by_group by_age by_git duplicates empty
+                cleanable format]
+            "Print a tree of what grew or shrank since the snapshot in SNAPSHOT was saved, \
+                largest change first. The current sizes come from scanning DIR or from --load. \
+                -n and --min-size apply to the changes rather than the sizes."
+        )
+
+        (@arg watch:
+            --watch [INTERVAL]
+            conflicts_with[interactive files dirs by_extension by_owner by_group by_age by_git
+                duplicates empty cleanable diff load save format percent bar color tree_style]
+            {|value| utils::parse_interval(&value).map(|_| ())}
+            "Scan again every INTERVAL and redraw the tree, showing how much every entry grew or \
+                shrank since the scan before and how fast. INTERVAL is a number of seconds, or \
+                a number followed by a unit like for --older-than. Every scan walks the whole \
+                tree again, but reuses what is known about the directories that have not changed \
+                like --cache does, instead of waiting for the file system to report changes. The \
+                tree is drawn plainly, so --percent, --bar, --color and --tree-style cannot be \
+                used."
+        )
+
+        (@arg percent:
+            --percent
+            "Show how large every entry is compared to its parent and to the root."
+        )
+
+        (@arg bar:
+            --bar
+            "Show a bar graph of how large every entry is compared to its parent. The bar is \
+                scaled to fit the width of the terminal."
+        )
+
+        (@arg iec:
+            --iec
+            "Show sizes in powers of 1024 (KiB, MiB, GiB, ...) instead of powers of 1000."
+        )
+
+        (@arg bytes:
+            --bytes
+            conflicts_with[iec]
+            "Show sizes as exact byte counts."
+        )
+
+        (@arg block_size:
+            long("block-size") [SIZE]
+            conflicts_with[iec bytes]
+            {|value| utils::parse_block_size(&value).map(|_| ())}
+            "Show sizes as the number of blocks of SIZE, rounded up. SIZE is a number with an \
+                optional unit, like \"512\", \"4K\" (powers of 1024) or \"1MB\" (powers of \
+                1000)."
+        )
+
+        (@arg precision:
+            --precision [DIGITS]
+            {|value| {
+                match value.parse::<usize>() {
+                    Ok(number) if number <= 10 => Ok(()),
+                    _ => Err("Precision must be an integer between 0 and 10.".to_string())
+                }
+            }}
+            "The number of decimals to show for sizes. Defaults to 2."
+        )
+
+        (@arg group_digits:
+            long("group-digits")
+            "Group the digits of sizes in thousands, using the separator of the current locale."
+        )
+
+        (@arg digit_separator:
+            long("digit-separator") [SEPARATOR]
+            {|value| {
+                if value.is_empty() {
+                    Err("The digit separator cannot be empty.".to_string())
+                } else {
+                    Ok(())
+                }
+            }}
+            "Group the digits of sizes in thousands using SEPARATOR instead of the separator of \
+                the locale."
+        )
+
+        (@arg color:
+            --color [WHEN]
+            possible_value[auto always never]
+            "When to color the output. Names are colored using LS_COLORS and sizes by how large \
+                they are. Defaults to auto, which colors the output of terminals unless NO_COLOR \
+                is set."
+        )
+
+        (@arg tree_style:
+            long("tree-style") [STYLE]
+            possible_value[indent unicode ascii]
+            "How to draw the tree. \"indent\" (the default) indents children with spaces, \
+                \"unicode\" and \"ascii\" draw connecting lines like tree(1) and align the \
+                sizes in a column."
+        )
+
+        (@arg format:
+            --format [FORMAT]
+            possible_value[text folded html svg]
+            conflicts_with[files dirs by_extension by_owner by_group by_age by_git duplicates empty
+                cleanable]
+            "The output format. \"text\" (the default) prints a human readable listing, \
+                \"folded\" prints one line per leaf entry in the folded stack format used by \
+                flame graph tools, \"html\" prints a self-contained HTML page with a zoomable \
+                treemap and \"svg\" prints a static image. Svg draws the entries within --depth, \
+                -n and --min-size. Html includes everything, or everything within --depth when \
+                it is given. Formats only apply to the tree, so they cannot be combined with the \
+                other modes."
+        )
+
+        (@arg layout:
+            --layout [LAYOUT]
+            possible_value[treemap sunburst]
+            "The layout of the SVG image. Defaults to treemap."
+        )
+
+        (@arg color_by:
+            long("color-by") [COLORING]
+            possible_value[depth extension]
+            "How to color the entries in the SVG image. Defaults to depth."
+        )
+
+        (@arg width:
+            --width [PIXELS]
+            {|value| {
+                match value.parse::<usize>() {
+                    Ok(number) if number > 0 => Ok(()),
+                    _ => Err("Width must be a positive integer.".to_string())
+                }
+            }}
+            "The width of the SVG image. Defaults to 1024."
+        )
+
+        (@arg height:
+            --height [PIXELS]
+            {|value| {
+                match value.parse::<usize>() {
+                    Ok(number) if number > 0 => Ok(()),
+                    _ => Err("Height must be a positive integer.".to_string())
+                }
+            }}
+            "The height of the SVG image. Defaults to 768."
+        )
+    ).get_matches_from_safe(iterator)?;
 
     let roots = matches.values_of("DIR").unwrap_or(vec!["."]);
+    let mut builder = Options::builder()
+        .roots(roots.iter().map(PathBuf::from).collect());
 
-    let default_depth = if matches.is_present("recursive") {
-        Depth::Unlimited
-    } else {
-        Depth::Limited(1)
-    };
+    // Bars are fitted to the terminal that is printed to.
+    if let Some(width) = terminal::width() {
+        builder = builder.width(width);
+    }
 
-    let depth = match matches.is_present("depth") {
-        true => {
-            // We can unwrap since the argument is required. We'd never get here unless the value
-            // exists.
-            let value = matches.value_of("depth").unwrap();
-            value.parse::<Depth>().unwrap_or_else(|error| {
-                println!("Could not determine depth: {}", error);
-                exit(2);
-            })
-        },
-        false => default_depth
-    };
+    // Without a depth, html has everything in it and the rest shows the children of the root.
+    if matches.is_present("depth") {
+        // We can unwrap since the argument is required. We'd never get here unless the value
+        // exists.
+        let value = matches.value_of("depth").unwrap();
+        builder = builder.depth(value.parse::<Depth>().unwrap_or_else(|error| {
+            println!("Could not determine depth: {}", error);
+            exit(2);
+        }));
+    } else if matches.is_present("recursive") {
+        builder = builder.depth(Depth::Unlimited);
+    }
 
     let limit = matches.value_of("limit").unwrap_or("1")
         .parse::<Limit>().unwrap_or_else(|error| {
@@ -189,25 +451,175 @@ fn parse_from<I, T>(iterator: I) -> Options
             exit(2);
         });
 
-    let mode = match matches.is_present("files") {
-        true => Mode::Files,
-        false => Mode::Tree,
+    let mode = if matches.is_present("interactive") {
+        Mode::Interactive
+    } else if matches.is_present("files") {
+        Mode::Files
+    } else if matches.is_present("dirs") {
+        Mode::Dirs
+    } else if matches.is_present("by_extension") {
+        Mode::Extensions
+    } else if matches.is_present("by_owner") {
+        Mode::Owners
+    } else if matches.is_present("by_group") {
+        Mode::Groups
+    } else if matches.is_present("by_age") {
+        Mode::Ages
+    } else if matches.is_present("by_git") {
+        Mode::Git
+    } else if matches.is_present("duplicates") {
+        Mode::Duplicates
+    } else if matches.is_present("empty") {
+        Mode::Empty
+    } else if matches.is_present("cleanable") {
+        Mode::Cleanable
+    } else {
+        Mode::Tree
     };
 
-    Options{
-        roots: roots.iter().map(|value| value.to_string()).collect(),
-        limit: limit,
-        depth: depth,
-        mode: mode,
-        show_all: matches.is_present("all"),
+    if let Some(name) = matches.value_of("owner") {
+        builder = builder.owner(Accounts::users().id(name).unwrap_or_else(|error| {
+            println!("Could not determine owner: {}", error);
+            exit(2);
+        }));
+    }
+
+    // The validators have already made sure that the ages can be parsed.
+    if let Some(value) = matches.value_of("older_than") {
+        builder = builder.older_than(utils::parse_age(value).unwrap());
+    }
+    if let Some(value) = matches.value_of("newer_than") {
+        builder = builder.newer_than(utils::parse_age(value).unwrap());
+    }
+
+    let timestamp = match matches.is_present("atime") {
+        true => Timestamp::Accessed,
+        false => Timestamp::Modified,
+    };
+
+    if let Some(value) = matches.value_of("age_buckets") {
+        // The validator has already made sure that the buckets can be parsed.
+        builder = builder.age_buckets(AgeBuckets::new(utils::parse_age_buckets(value).unwrap()));
+    }
+
+    let mut cleanable_rules = cleanable::default_rules();
+    if let Some(value) = matches.value_of("cleanable_rules") {
+        // The validator has already made sure that the rules can be parsed.
+        cleanable_rules.extend(cleanable::parse_rules(value).unwrap());
+    }
+
+    if let Some(value) = matches.value_of("save") {
+        builder = builder.snapshot_to_save(PathBuf::from(value));
+    }
+    if let Some(value) = matches.value_of("load") {
+        builder = builder.snapshot_to_load(PathBuf::from(value));
+    }
+    if let Some(value) = matches.value_of("diff") {
+        builder = builder.snapshot_to_diff(PathBuf::from(value));
     }
+    if let Some(value) = matches.value_of("watch") {
+        // The validator has already made sure that the interval can be parsed.
+        let seconds = utils::parse_interval(value).unwrap();
+        builder = builder.watch_interval(Duration::from_secs(seconds));
+    }
+    if let Some(value) = matches.value_of("cache") {
+        builder = builder.cache_file(PathBuf::from(value));
+    }
+
+    let format = matches.value_of("format").unwrap_or("text")
+        .parse::<Format>().unwrap_or_else(|error| {
+            println!("Could not determine format: {}", error);
+            exit(2);
+        });
+
+    let tree_style = matches.value_of("tree_style").unwrap_or("indent")
+        .parse::<TreeStyle>().unwrap_or_else(|error| {
+            println!("Could not determine tree style: {}", error);
+            exit(2);
+        });
+
+    let color = matches.value_of("color").unwrap_or("auto")
+        .parse::<ColorChoice>().unwrap_or_else(|error| {
+            println!("Could not determine color choice: {}", error);
+            exit(2);
+        });
+
+    let units = match matches.value_of("block_size") {
+        // The validator has already made sure that the block size can be parsed.
+        Some(value) => Units::Blocks(utils::parse_block_size(value).unwrap()),
+        None if matches.is_present("bytes") => Units::Bytes,
+        None if matches.is_present("iec") => Units::Binary,
+        None => Units::Decimal,
+    };
+
+    let grouping = match matches.value_of("digit_separator") {
+        Some(separator) => Some(DigitGrouping::with_separator(separator)),
+        None if matches.is_present("group_digits") => Some(DigitGrouping::from_env()),
+        None => None,
+    };
+
+    let size_format = SizeFormat {
+        units: units,
+        precision: matches.value_of("precision").unwrap_or("2").parse::<usize>().unwrap(),
+        grouping: grouping,
+    };
+
+    let svg_layout = matches.value_of("layout").unwrap_or("treemap")
+        .parse::<Layout>().unwrap_or_else(|error| {
+            println!("Could not determine layout: {}", error);
+            exit(2);
+        });
+
+    let svg_coloring = matches.value_of("color_by").unwrap_or("depth")
+        .parse::<Coloring>().unwrap_or_else(|error| {
+            println!("Could not determine coloring: {}", error);
+            exit(2);
+        });
+
+    // The validators have already made sure these are positive integers.
+    let svg_width = matches.value_of("width").unwrap_or("1024").parse::<usize>().unwrap();
+    let svg_height = matches.value_of("height").unwrap_or("768").parse::<usize>().unwrap();
+
+    // The validator has already made sure that the size can be parsed.
+    let min_size = matches.value_of("min_size").map(|value| utils::parse_size(value).unwrap())
+        .unwrap_or(0);
+
+    Ok(builder
+        .limit(limit)
+        .mode(mode)
+        .format(format)
+        .tree_style(tree_style)
+        .svg_layout(svg_layout)
+        .svg_coloring(svg_coloring)
+        .svg_size(svg_width, svg_height)
+        .show_hidden(matches.is_present("all"))
+        .show_percent(matches.is_present("percent"))
+        .show_bar(matches.is_present("bar"))
+        .rank_cumulative(matches.is_present("cumulative"))
+        .show_absolute_paths(matches.is_present("absolute"))
+        .group_per_child(matches.is_present("per_child"))
+        .min_size(min_size)
+        .timestamp(timestamp)
+        .separate_with_nul(matches.is_present("null"))
+        .cleanable_rules(cleanable_rules)
+        .look_into_archives(matches.is_present("into_archives"))
+        .allow_delete(matches.is_present("allow_delete"))
+        .palette(Palette::from_env(&color))
+        .size_format(size_format)
+        .build())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Depth,Limit,parse_from};
+    use super::{parse_from, try_parse_from};
     use std::path::PathBuf;
-    use modes::Mode;
+    use std::time::Duration;
+    use dutop::breakdown::AgeBuckets;
+    use dutop::cleanable::{self, Rule};
+    use dutop::modes::TreeStyle;
+    use dutop::svg::{Coloring, Layout};
+    use dutop::utils::{DigitGrouping, SizeFormat, Units};
+    use dutop::{Format, Mode, Timestamp};
 
     // parse_from and Option
 
@@ -215,9 +627,7 @@ mod tests {
     fn it_has_defaults_on_no_arguments() {
         let options = parse_from(vec!["dutop"]);
 
-        assert_eq!(options.roots, vec!["."]);
-        assert_eq!(options.limit, Limit::Limited(1));
-        assert_eq!(options.depth, Depth::Limited(1));
+        assert_eq!(options.roots(), vec![PathBuf::from(".")]);
         assert_eq!(options.should_show_hidden(), false);
 
         assert_eq!(options.limit_reached(0), false);
@@ -230,20 +640,19 @@ mod tests {
     #[test]
     fn it_takes_multiple_roots() {
         let options = parse_from(vec!["dutop", "foo", "bar"]);
-        assert_eq!(options.roots, vec!["foo", "bar"]);
         assert_eq!(options.roots(), vec![PathBuf::from("foo"), PathBuf::from("bar")]);
     }
 
     #[test]
     fn options_has_depth_information() {
         let options = parse_from(vec!["dutop", "-d", "0"]);
-        assert_eq!(options.depth, Depth::Unlimited);
+        assert_eq!(options.depth_accepts(100), true);
     }
 
     #[test]
     fn options_has_limit_information() {
         let options = parse_from(vec!["dutop", "-n", "0"]);
-        assert_eq!(options.limit, Limit::Unlimited);
+        assert_eq!(options.limit_reached(100), false);
     }
 
     #[test]
@@ -255,13 +664,14 @@ mod tests {
     #[test]
     fn options_defaults_to_unlimited_depth_when_recursive() {
         let options = parse_from(vec!["dutop", "-r"]);
-        assert_eq!(options.depth, Depth::Unlimited);
+        assert_eq!(options.depth_accepts(100), true);
     }
 
     #[test]
     fn options_can_override_depth_when_recursive() {
         let options = parse_from(vec!["dutop", "-d", "5", "-r"]);
-        assert_eq!(options.depth, Depth::Limited(5));
+        assert_eq!(options.depth_accepts(4), true);
+        assert_eq!(options.depth_accepts(5), false);
     }
 
     #[test]
@@ -276,53 +686,296 @@ mod tests {
         assert_eq!(options.mode(), &Mode::Files);
     }
 
-    // Depth
+    #[test]
+    fn options_can_select_directory_mode() {
+        let options = parse_from(vec!["dutop", "--dirs"]);
+        assert_eq!(options.mode(), &Mode::Dirs);
+        assert_eq!(options.should_rank_cumulative(), false);
+
+        let options = parse_from(vec!["dutop", "--dirs", "--cumulative"]);
+        assert_eq!(options.should_rank_cumulative(), true);
+    }
 
     #[test]
-    fn it_parses_positive_depth_from_strings() {
-        assert_eq!("12".parse::<Depth>(), Ok(Depth::Limited(12)));
+    fn options_can_select_extension_mode() {
+        let options = parse_from(vec!["dutop", "--by-extension"]);
+        assert_eq!(options.mode(), &Mode::Extensions);
+        assert_eq!(options.should_group_per_child(), false);
+
+        let options = parse_from(vec!["dutop", "--by-extension", "--per-child"]);
+        assert_eq!(options.should_group_per_child(), true);
+        let options = parse_from(vec!["dutop", "--by-group", "--per-child"]);
+        assert_eq!(options.should_group_per_child(), true);
     }
 
     #[test]
-    fn it_parses_zero_depth_from_strings() {
-        assert_eq!("0".parse::<Depth>(), Ok(Depth::Unlimited));
+    fn options_can_select_owner_and_group_modes() {
+        assert_eq!(parse_from(vec!["dutop", "--by-owner"]).mode(), &Mode::Owners);
+        assert_eq!(parse_from(vec!["dutop", "--by-group"]).mode(), &Mode::Groups);
     }
 
     #[test]
-    fn it_parses_named_alias_for_unlimited_depth_from_strings() {
-        assert_eq!("all".parse::<Depth>(), Ok(Depth::Unlimited));
+    fn options_can_filter_by_owner() {
+        assert_eq!(parse_from(vec!["dutop"]).owner(), None);
+        assert_eq!(parse_from(vec!["dutop", "--owner", "4321"]).owner(), Some(4321));
     }
 
     #[test]
-    fn it_rejects_broken_depth_strings() {
-        assert_eq!(
-            "totally broken".parse::<Depth>(),
-            Err("Not a positive integer or \"all\"".to_string())
-        );
+    fn options_can_select_age_mode() {
+        let defaults = parse_from(vec!["dutop"]);
+        assert_eq!(defaults.timestamp(), Timestamp::Modified);
+        assert_eq!(defaults.age_buckets(), &AgeBuckets::default());
+
+        let options = parse_from(vec!["dutop", "--by-age", "--atime", "--age-buckets", "7,30"]);
+        assert_eq!(options.mode(), &Mode::Ages);
+        assert_eq!(options.timestamp(), Timestamp::Accessed);
+        assert_eq!(options.age_buckets(), &AgeBuckets::new(vec![7, 30]));
+    }
+
+    #[test]
+    fn options_can_select_git_mode() {
+        assert_eq!(parse_from(vec!["dutop", "--by-git"]).mode(), &Mode::Git);
+    }
+
+    #[test]
+    fn options_can_select_cleanable_mode_with_more_rules() {
+        let defaults = parse_from(vec!["dutop", "--cleanable"]);
+        assert_eq!(defaults.mode(), &Mode::Cleanable);
+        assert_eq!(defaults.cleanable_rules(), &cleanable::default_rules()[..]);
+
+        let options = parse_from(vec![
+            "dutop", "--cleanable", "--cleanable-rules", "dist:setup.py",
+        ]);
+        let rules = options.cleanable_rules();
+        assert_eq!(rules.len(), cleanable::default_rules().len() + 1);
+        assert_eq!(rules.last(), Some(&Rule {
+            name: "dist".to_string(),
+            markers: vec!["setup.py".to_string()],
+        }));
+    }
+
+    #[test]
+    fn options_can_select_duplicate_mode() {
+        assert_eq!(parse_from(vec!["dutop", "--duplicates"]).mode(), &Mode::Duplicates);
+    }
+
+    #[test]
+    fn options_can_select_empty_mode() {
+        let options = parse_from(vec!["dutop", "--empty"]);
+        assert_eq!(options.mode(), &Mode::Empty);
+        assert_eq!(options.should_separate_with_nul(), false);
+
+        let options = parse_from(vec!["dutop", "--empty", "--null"]);
+        assert_eq!(options.should_separate_with_nul(), true);
+    }
+
+    #[test]
+    fn options_can_save_and_load_snapshots() {
+        let defaults = parse_from(vec!["dutop"]);
+        assert_eq!(defaults.snapshot_to_save(), None);
+        assert_eq!(defaults.snapshot_to_load(), None);
+
+        let options = parse_from(vec!["dutop", "--save", "new.snapshot", "--load", "old"]);
+        assert_eq!(options.snapshot_to_save(), Some(&PathBuf::from("new.snapshot")));
+        assert_eq!(options.snapshot_to_load(), Some(&PathBuf::from("old")));
     }
 
-    // Limit
+    #[test]
+    fn options_can_diff_against_a_snapshot() {
+        assert_eq!(parse_from(vec!["dutop"]).snapshot_to_diff(), None);
+
+        let options = parse_from(vec!["dutop", "--diff", "old.snapshot", "src"]);
+        assert_eq!(options.snapshot_to_diff(), Some(&PathBuf::from("old.snapshot")));
+        assert_eq!(options.mode(), &Mode::Tree);
+    }
+
+    #[test]
+    fn options_can_keep_a_cache() {
+        assert_eq!(parse_from(vec!["dutop"]).cache_file(), None);
+        assert_eq!(parse_from(vec!["dutop", "--cache", "scan.cache"]).cache_file(),
+                   Some(&PathBuf::from("scan.cache")));
+    }
+
+    #[test]
+    fn options_can_look_into_archives() {
+        assert_eq!(parse_from(vec!["dutop"]).should_look_into_archives(), false);
+        assert_eq!(parse_from(vec!["dutop", "--into-archives"]).should_look_into_archives(), true);
+    }
+
+    #[test]
+    fn options_can_watch_with_an_interval() {
+        assert_eq!(parse_from(vec!["dutop"]).watch_interval(), None);
+        assert_eq!(parse_from(vec!["dutop", "--watch", "5"]).watch_interval(),
+                   Some(Duration::from_secs(5)));
+        assert_eq!(parse_from(vec!["dutop", "--watch", "1m"]).watch_interval(),
+                   Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn options_can_filter_by_age() {
+        let defaults = parse_from(vec!["dutop"]);
+        assert_eq!(defaults.older_than(), None);
+        assert_eq!(defaults.newer_than(), None);
+
+        let options = parse_from(vec!["dutop", "--older-than", "30d", "--newer-than", "1y"]);
+        assert_eq!(options.older_than(), Some(30 * 24 * 60 * 60));
+        assert_eq!(options.newer_than(), Some(365 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn options_has_a_minimum_size() {
+        let defaults = parse_from(vec!["dutop"]);
+        assert_eq!(defaults.is_large_enough(0), true);
+
+        let options = parse_from(vec!["dutop", "--min-size", "2K"]);
+        assert_eq!(options.is_large_enough(2047), false);
+        assert_eq!(options.is_large_enough(2048), true);
+    }
 
     #[test]
-    fn it_parses_positive_limit_from_strings() {
-        assert_eq!("12".parse::<Limit>(), Ok(Limit::Limited(12)));
+    fn options_can_select_interactive_mode() {
+        let options = parse_from(vec!["dutop", "-i"]);
+        assert_eq!(options.mode(), &Mode::Interactive);
+        assert_eq!(options.should_allow_delete(), false);
+
+        let options = parse_from(vec!["dutop", "--interactive", "--allow-delete"]);
+        assert_eq!(options.mode(), &Mode::Interactive);
+        assert_eq!(options.should_allow_delete(), true);
     }
 
     #[test]
-    fn it_parses_zero_limit_from_strings() {
-        assert_eq!("0".parse::<Limit>(), Ok(Limit::Unlimited));
+    fn options_can_enable_percent_and_bar_columns() {
+        let defaults = parse_from(vec!["dutop"]);
+        assert_eq!(defaults.should_show_percent(), false);
+        assert_eq!(defaults.should_show_bar(), false);
+
+        let options = parse_from(vec!["dutop", "--percent", "--bar"]);
+        assert_eq!(options.should_show_percent(), true);
+        assert_eq!(options.should_show_bar(), true);
     }
 
     #[test]
-    fn it_parses_named_alias_for_unlimited_limit_from_strings() {
-        assert_eq!("all".parse::<Limit>(), Ok(Limit::Unlimited));
+    fn options_can_select_colors() {
+        assert_eq!(parse_from(vec!["dutop", "--color", "always"]).palette().is_enabled(), true);
+        assert_eq!(parse_from(vec!["dutop", "--color", "never"]).palette().is_enabled(), false);
     }
 
     #[test]
-    fn it_rejects_broken_limit_strings() {
+    fn options_default_to_decimal_sizes() {
+        let options = parse_from(vec!["dutop"]);
+        assert_eq!(options.size_format(), &SizeFormat::default());
+    }
+
+    #[test]
+    fn options_can_select_size_units() {
+        assert_eq!(parse_from(vec!["dutop", "--iec"]).size_format().units, Units::Binary);
+        assert_eq!(parse_from(vec!["dutop", "--bytes"]).size_format().units, Units::Bytes);
         assert_eq!(
-            "totally broken".parse::<Limit>(),
-            Err("Not a positive integer or \"all\"".to_string())
+            parse_from(vec!["dutop", "--block-size", "4K"]).size_format().units,
+            Units::Blocks(4096)
         );
     }
+
+    #[test]
+    fn options_can_change_size_precision() {
+        let options = parse_from(vec!["dutop", "--precision", "0"]);
+        assert_eq!(options.size_format().precision, 0);
+    }
+
+    #[test]
+    fn options_can_group_digits_with_a_given_separator() {
+        let options = parse_from(vec!["dutop", "--digit-separator", " "]);
+        assert_eq!(options.size_format().grouping, Some(DigitGrouping::with_separator(" ")));
+    }
+
+    #[test]
+    fn options_do_not_group_digits_by_default() {
+        let options = parse_from(vec!["dutop"]);
+        assert_eq!(options.size_format().grouping, None);
+
+        let options = parse_from(vec!["dutop", "--group-digits"]);
+        assert!(options.size_format().grouping.is_some());
+    }
+
+    #[test]
+    fn options_default_to_indented_trees() {
+        let options = parse_from(vec!["dutop"]);
+        assert_eq!(options.tree_style(), &TreeStyle::Indent);
+    }
+
+    #[test]
+    fn options_can_select_tree_style() {
+        let options = parse_from(vec!["dutop", "--tree-style", "unicode"]);
+        assert_eq!(options.tree_style(), &TreeStyle::Unicode);
+    }
+
+    #[test]
+    fn options_default_to_text_format() {
+        let options = parse_from(vec!["dutop"]);
+        assert_eq!(options.format(), &Format::Text);
+    }
+
+    #[test]
+    fn options_can_select_folded_format() {
+        let options = parse_from(vec!["dutop", "--format", "folded"]);
+        assert_eq!(options.format(), &Format::Folded);
+    }
+
+    #[test]
+    fn options_can_select_html_format() {
+        let options = parse_from(vec!["dutop", "--format", "html"]);
+        assert_eq!(options.format(), &Format::Html);
+    }
+
+    #[test]
+    fn options_refuse_to_watch_with_columns_colors_or_tree_styles() {
+        let combinations = vec![
+            vec!["dutop", "--watch", "5", "--percent"],
+            vec!["dutop", "--watch", "5", "--bar"],
+            vec!["dutop", "--watch", "5", "--color", "always"],
+            vec!["dutop", "--watch", "5", "--tree-style", "unicode"],
+        ];
+        for arguments in combinations {
+            assert!(try_parse_from(arguments.clone()).is_err(), "{:?} was accepted", arguments);
+        }
+    }
+
+    #[test]
+    fn options_refuse_formats_with_other_modes() {
+        let combinations = vec![
+            vec!["dutop", "--format", "folded", "--files"],
+            vec!["dutop", "--format", "text", "--dirs"],
+            vec!["dutop", "--format", "html", "--by-extension"],
+            vec!["dutop", "--format", "html", "--by-owner"],
+            vec!["dutop", "--format", "svg", "--by-group"],
+            vec!["dutop", "--format", "folded", "--by-age"],
+            vec!["dutop", "--format", "folded", "--by-git"],
+            vec!["dutop", "--format", "svg", "--duplicates"],
+            vec!["dutop", "--format", "svg", "--empty"],
+            vec!["dutop", "--format", "html", "--cleanable"],
+        ];
+        for arguments in combinations {
+            assert!(try_parse_from(arguments.clone()).is_err(), "{:?} was accepted", arguments);
+        }
+
+        assert!(try_parse_from(vec!["dutop", "--format", "folded", "--depth", "2"]).is_ok());
+    }
+
+    #[test]
+    fn options_have_svg_defaults() {
+        let options = parse_from(vec!["dutop", "--format", "svg"]);
+        assert_eq!(options.format(), &Format::Svg);
+        assert_eq!(options.svg_layout(), &Layout::Treemap);
+        assert_eq!(options.svg_coloring(), &Coloring::Depth);
+        assert_eq!(options.svg_size(), (1024, 768));
+    }
+
+    #[test]
+    fn options_can_configure_svg_output() {
+        let options = parse_from(vec!["dutop", "--layout", "sunburst", "--color-by", "extension",
+                                      "--width", "640", "--height", "480"]);
+        assert_eq!(options.svg_layout(), &Layout::Sunburst);
+        assert_eq!(options.svg_coloring(), &Coloring::Extension);
+        assert_eq!(options.svg_size(), (640, 480));
+    }
 }
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use entry::{Entry, Timestamp};
+use modes::DisplayableEntry;
+use utils::{self, SECONDS_PER_DAY};
+
+// The files of a tree that share something, like their extension.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Group {
+    pub name: String,
+    pub size: u64,
+    pub count: u64,
+}
+
+// Adds up all files below the entry by the key they are given, largest group first. Hidden files
+// are always included, since they take up room too. Archives count as a single file, with the room
+// they take up on disk.
+pub fn group_files<F>(entry: &Entry, key: F) -> Vec<Group> where F: Fn(&Entry) -> String {
+    let mut totals: HashMap<String, (u64, u64)> = HashMap::new();
+    add_files(entry, &key, &mut totals);
+
+    let mut groups: Vec<Group> = totals.into_iter()
+        .map(|(name, (size, count))| Group { name: name, size: size, count: count })
+        .collect();
+
+    // Ties are sorted by name so the output does not depend on the order of the hash map.
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    groups
+}
+
+fn add_files<F>(entry: &Entry, key: &F, totals: &mut HashMap<String, (u64, u64)>)
+    where F: Fn(&Entry) -> String {
+    if entry.is_file() || entry.is_archive() {
+        let total = totals.entry(key(entry)).or_insert((0, 0));
+        total.0 += entry.size();
+        total.1 += 1;
+    } else {
+        for child in entry.children_iter() {
+            add_files(child, key, totals);
+        }
+    }
+}
+
+// Ages are put in buckets by limits in days, so "30,90,365" gives the buckets "0-30 days",
+// "30-90 days", "90-365 days" and "365+ days".
+#[derive(Debug, PartialEq, Eq)]
+pub struct AgeBuckets {
+    days: Vec<u64>,
+}
+
+impl Default for AgeBuckets {
+    fn default() -> AgeBuckets {
+        AgeBuckets::new(vec![30, 90, 365])
+    }
+}
+
+impl AgeBuckets {
+    pub fn new(days: Vec<u64>) -> AgeBuckets {
+        AgeBuckets { days: days }
+    }
+
+    // The age is given in seconds.
+    pub fn label(&self, age: u64) -> String {
+        let index = self.days.iter()
+            .position(|&days| age < days * SECONDS_PER_DAY)
+            .unwrap_or(self.days.len());
+        self.label_at(index)
+    }
+
+    fn label_at(&self, index: usize) -> String {
+        if index == self.days.len() {
+            format!("{}+ days", self.days[index - 1])
+        } else if index == 0 {
+            format!("0-{} days", self.days[0])
+        } else {
+            format!("{}-{} days", self.days[index - 1], self.days[index])
+        }
+    }
+
+    fn position(&self, label: &str) -> usize {
+        (0..self.days.len() + 1).position(|index| self.label_at(index) == label).unwrap_or(0)
+    }
+}
+
+// Like group_files, but the groups are sorted from the newest to the oldest.
+pub fn group_by_age(entry: &Entry,
+                    buckets: &AgeBuckets,
+                    timestamp: Timestamp,
+                    now: i64) -> Vec<Group> {
+    let mut groups = group_files(entry, |file| buckets.label(file.age(timestamp, now)));
+    groups.sort_by_key(|group| buckets.position(&group.name));
+    groups
+}
+
+// Archives have a slash after their name, like directories.
+pub fn extension_key(entry: &Entry) -> String {
+    match utils::file_extension(entry.name().trim_end_matches('/')) {
+        Some(extension) => format!(".{}", extension),
+        None => "(no extension)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archives::tests::tar;
+    use entry::Entry;
+    use filesystem::MemoryFileSystem;
+    use modes::DisplayableEntry;
+    use root::Root;
+    use std::path::Path;
+
+    #[test]
+    fn it_groups_files_by_extension() {
+        let entry = Entry::for_path(Path::new("./src")).unwrap();
+        let groups = group_files(&entry, extension_key);
+
+        let rust = groups.iter().find(|group| group.name == ".rs").unwrap();
+        let html = groups.iter().find(|group| group.name == ".html").unwrap();
+        assert_eq!(html.count, 1);
+        assert_eq!(rust.count as usize, entry.children_iter().count() - 1);
+        assert_eq!(groups[0].name, ".rs");
+
+        let files_size: u64 = entry.children_iter().map(|child| child.size()).sum();
+        assert_eq!(rust.size + html.size, files_size);
+    }
+
+    #[test]
+    fn it_counts_archives_as_files_with_their_size_on_disk() {
+        let archive = tar(&[("notes.txt", &[b'a'; 5000]), ("more.txt", &[b'b'; 5000])]);
+        let fs = MemoryFileSystem::new()
+            .contents("project/backup.tar", archive)
+            .file("project/readme.txt", 100);
+        let root = Root::scan_path(&fs, Path::new("project"), true).unwrap();
+        let archive_size = root.children_iter().find(|child| child.is_archive()).unwrap().size();
+
+        assert_eq!(group_files(root.entry(), extension_key), vec![
+            Group { name: ".tar".to_string(), size: archive_size, count: 1 },
+            Group { name: ".txt".to_string(), size: 100, count: 1 },
+        ]);
+    }
+
+    #[test]
+    fn it_labels_age_buckets() {
+        let buckets = AgeBuckets::default();
+        let days = |days: u64| days * SECONDS_PER_DAY;
+
+        assert_eq!(buckets.label(0), "0-30 days");
+        assert_eq!(buckets.label(days(30) - 1), "0-30 days");
+        assert_eq!(buckets.label(days(30)), "30-90 days");
+        assert_eq!(buckets.label(days(100)), "90-365 days");
+        assert_eq!(buckets.label(days(1000)), "365+ days");
+        assert_eq!(AgeBuckets::new(vec![7]).label(days(8)), "7+ days");
+    }
+
+    #[test]
+    fn it_groups_files_by_age_from_new_to_old() {
+        let entry = Entry::for_path(Path::new("./src")).unwrap();
+        let newest = entry.children_iter().map(|child| child.time(Timestamp::Modified)).max();
+        let now = newest.unwrap() + 45 * SECONDS_PER_DAY as i64;
+
+        let groups = group_by_age(&entry, &AgeBuckets::default(), Timestamp::Modified, now);
+        assert!(groups.len() >= 1);
+        assert_eq!(groups[0].name, "30-90 days");
+        assert_eq!(groups.iter().map(|group| group.count).sum::<u64>(),
+                   entry.children_iter().count() as u64);
+    }
+
+    #[test]
+    fn it_groups_files_without_extension() {
+        let entry = Entry::for_path(Path::new("./LICENSE")).unwrap();
+        let groups = group_files(&entry, extension_key);
+
+        assert_eq!(groups, vec![
+            Group { name: "(no extension)".to_string(), size: entry.size(), count: 1 },
+        ]);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use filesystem::FileSystem;
+use root::Root;
+use snapshot::{Decoder, Encoder};
+use utils;
+
+// Cache files start with this, followed by the settings the scans in them were made with.
+const HEADER: &'static str = "dutop cache 3";
+
+// The last scan of every root, so scanning a root again only has to look at what changed since
+// then. Roots are kept by the path they were scanned with, together with when the scan started.
+#[derive(Debug)]
+pub struct Cache {
+    settings: String,
+    roots: HashMap<PathBuf, (i64, Root)>,
+}
+
+impl Cache {
+    pub fn new() -> Cache {
+        Cache { settings: String::new(), roots: HashMap::new() }
+    }
+
+    // A cache that is missing or cannot be read is started over. So is one that was made with
+    // other settings, once it is used to scan.
+    pub fn load(path: &Path) -> Cache {
+        File::open(path).ok()
+            .and_then(|file| read_cache(&mut Decoder::new(BufReader::new(file))).ok())
+            .unwrap_or_else(Cache::new)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(utils::describe_io_error)?;
+        let mut encoder = Encoder::new(BufWriter::new(file));
+        self.write_cache(&mut encoder).map_err(utils::describe_io_error)
+    }
+
+    // Returns a copy of the scan, since the cache keeps it for the next time.
+    pub fn scan<F: FileSystem>(&mut self,
+                               fs: &F,
+                               path: &Path,
+                               into_archives: bool) -> Result<Root, String> {
+        let settings = settings(into_archives);
+        if settings != self.settings {
+            self.roots.clear();
+            self.settings = settings;
+        }
+
+        let started = utils::now();
+        let root = match self.roots.remove(path) {
+            Some((scanned_at, previous)) => {
+                Root::rescan(fs, path, previous, scanned_at, into_archives)?
+            },
+            None => Root::scan_path(fs, path, into_archives)?,
+        };
+
+        self.roots.insert(path.to_path_buf(), (started, root.clone()));
+        Ok(root)
+    }
+
+    fn write_cache<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_str(HEADER)?;
+        encoder.write_str(&self.settings)?;
+        encoder.write_u64(self.roots.len() as u64)?;
+        for (path, &(scanned_at, ref root)) in self.roots.iter() {
+            encoder.write_str(&path.to_string_lossy())?;
+            encoder.write_i64(scanned_at)?;
+            root.encode(encoder)?;
+        }
+        encoder.flush()
+    }
+}
+
+fn read_cache<R: Read>(decoder: &mut Decoder<R>) -> Result<Cache, String> {
+    if decoder.read_string()? != HEADER {
+        return Err("Not a usable cache".to_string());
+    }
+    let settings = decoder.read_string()?;
+
+    let mut roots = HashMap::new();
+    for _ in 0..decoder.read_u64()? {
+        let path = PathBuf::from(decoder.read_string()?);
+        let scanned_at = decoder.read_i64()?;
+        roots.insert(path, (scanned_at, Root::decode(decoder)?));
+    }
+    Ok(Cache { settings: settings, roots: roots })
+}
+
+// Describes everything that changes what a scan finds. A cache made with other settings could be
+// missing entries or have entries that should not be there, so it is not used.
+fn settings(into_archives: bool) -> String {
+    let archives = if into_archives { " --into-archives" } else { "" };
+    format!("dutop {}{}", env!("CARGO_PKG_VERSION"), archives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filesystem::MemoryFileSystem;
+    use modes::DisplayableEntry;
+    use utils::tests::TempDir;
+
+    fn names(root: &Root) -> Vec<String> {
+        let mut names: Vec<String> = root.children_iter()
+            .flat_map(|child| child.children_iter())
+            .map(|child| child.name().clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn it_only_reads_directories_that_changed() {
+        let root = Path::new("root");
+        let mut cache = Cache::new();
+        let first = MemoryFileSystem::new().file("root/old/log", 100).times("root/old", 100, 100);
+        assert_eq!(names(&cache.scan(&first, root, false).unwrap()), vec!["log"]);
+
+        // The directory did not change, so only the file that was there is looked at.
+        let second = MemoryFileSystem::new()
+            .file("root/old/log", 300)
+            .file("root/old/new", 50)
+            .times("root/old", 100, 100);
+        let scanned = cache.scan(&second, root, false).unwrap();
+        assert_eq!(names(&scanned), vec!["log"]);
+        assert_eq!(scanned.size(), 300);
+
+        // Once it changes, it is read again.
+        let third = MemoryFileSystem::new()
+            .file("root/old/log", 300)
+            .file("root/old/new", 50)
+            .times("root/old", 200, 200);
+        assert_eq!(names(&cache.scan(&third, root, false).unwrap()), vec!["log", "new"]);
+
+        // So is a directory that changed in the same second as it was scanned.
+        let now = utils::now();
+        let fourth = MemoryFileSystem::new().file("recent/log", 10).times("recent", now, now);
+        cache.scan(&fourth, Path::new("recent"), false).unwrap();
+        let fifth = fourth.file("recent/new", 10).times("recent", now, now);
+        assert_eq!(cache.scan(&fifth, Path::new("recent"), false).unwrap().size(), 20);
+    }
+
+    #[test]
+    fn it_reads_directories_again_that_could_not_be_read() {
+        let root = Path::new("root");
+        let mut cache = Cache::new();
+        let locked = MemoryFileSystem::new().unreadable("root/old").times("root/old", 100, 100);
+        assert_eq!(cache.scan(&locked, root, false).unwrap().size(), 0);
+
+        // Fixing the permissions does not change the modification time.
+        let unlocked = MemoryFileSystem::new()
+            .file("root/old/log", 100)
+            .times("root/old", 100, 100);
+        assert_eq!(names(&cache.scan(&unlocked, root, false).unwrap()), vec!["log"]);
+    }
+
+    #[test]
+    fn it_saves_and_loads_scans() {
+        let directory = TempDir::new("cache");
+        let path = directory.join("cache");
+        let mut cache = Cache::new();
+        let fs = MemoryFileSystem::new().file("root/file", 10);
+        cache.scan(&fs, Path::new("root"), false).unwrap();
+
+        cache.save(&path).unwrap();
+        let loaded = Cache::load(&path);
+
+        assert_eq!(format!("{:?}", loaded.roots), format!("{:?}", cache.roots));
+        assert_eq!(Cache::load(Path::new("./LICENSE")).roots.len(), 0);
+    }
+
+    #[test]
+    fn it_starts_over_when_the_settings_change() {
+        let root = Path::new("root");
+        let mut cache = Cache::new();
+        let fs = MemoryFileSystem::new().file("root/old/log", 100).times("root/old", 100, 100);
+        cache.scan(&fs, root, false).unwrap();
+
+        let changed = fs.file("root/old/new", 50).times("root/old", 100, 100);
+        assert_eq!(names(&cache.scan(&changed, root, true).unwrap()), vec!["log", "new"]);
+        assert_eq!(cache.settings, settings(true));
+    }
+}
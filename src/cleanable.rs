@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+
+use entry::Entry;
+use modes::DisplayableEntry;
+
+// Directories that tools make and can make again, by name and the files that have to be next to
+// them. Names that are also used for other things, like "build", need a marker so only the ones
+// made by a build are matched.
+const DEFAULT_RULES: &'static [(&'static str, &'static [&'static str])] = &[
+    ("target", &["Cargo.toml", "pom.xml"]),
+    ("node_modules", &[]),
+    ("__pycache__", &[]),
+    (".pytest_cache", &[]),
+    (".mypy_cache", &[]),
+    (".tox", &["tox.ini"]),
+    (".gradle", &[]),
+    ("build", &["build.gradle", "build.gradle.kts", "CMakeLists.txt", "setup.py"]),
+    (".cache", &[]),
+];
+
+// A directory name that can be cleaned up. Without markers every directory with the name matches,
+// and with them only the ones that have one of the markers next to them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub markers: Vec<String>,
+}
+
+impl Rule {
+    fn matches(&self, parent: &Entry, name: &str) -> bool {
+        self.name == name && (self.markers.is_empty() || self.marker_in(parent).is_some())
+    }
+
+    fn marker_in(&self, parent: &Entry) -> Option<&String> {
+        self.markers.iter()
+            .find(|marker| parent.children_iter().any(|child| child.name() == *marker))
+    }
+}
+
+pub fn default_rules() -> Vec<Rule> {
+    DEFAULT_RULES.iter()
+        .map(|&(name, markers)| Rule {
+            name: name.to_string(),
+            markers: markers.iter().map(|marker| marker.to_string()).collect(),
+        })
+        .collect()
+}
+
+// Rules are separated by commas, and markers are given after a colon and separated by bars, like
+// "dist:package.json,vendor:composer.json|go.mod,.venv".
+pub fn parse_rules(value: &str) -> Result<Vec<Rule>, String> {
+    value.split(',').map(|rule| {
+        let mut parts = rule.splitn(2, ':');
+        let name = parts.next().unwrap_or("").trim().trim_end_matches('/');
+        if name.is_empty() || name.contains('/') {
+            return Err(format!("Invalid rule \"{}\"", rule));
+        }
+
+        let markers = parts.next().unwrap_or("").split('|')
+            .map(|marker| marker.trim())
+            .filter(|marker| !marker.is_empty())
+            .map(|marker| marker.to_string())
+            .collect();
+        Ok(Rule { name: name.to_string(), markers: markers })
+    }).collect()
+}
+
+// A directory that can be removed, with its path from the root and the marker that made it match.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cleanable {
+    pub path: PathBuf,
+    pub size: u64,
+    pub marker: Option<String>,
+}
+
+// Finds the directories below the entry that match a rule, largest first. What is in a matching
+// directory is not looked at, so the node_modules in node_modules are only counted once. Hidden
+// directories are always looked at, since many of them are caches.
+pub fn find_cleanable(entry: &Entry, rules: &[Rule]) -> Vec<Cleanable> {
+    let mut found = Vec::new();
+    collect_cleanable(entry, Path::new(""), rules, &mut found);
+    found.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    found
+}
+
+fn collect_cleanable(entry: &Entry, path: &Path, rules: &[Rule], found: &mut Vec<Cleanable>) {
+    for child in entry.children_iter().filter(|child| !child.is_file() && !child.is_archive()) {
+        let name = child.name().trim_end_matches('/');
+        let child_path = path.join(name);
+        match rules.iter().find(|rule| rule.matches(entry, name)) {
+            Some(rule) => found.push(Cleanable {
+                path: child_path,
+                size: child.size(),
+                marker: rule.marker_in(entry).cloned(),
+            }),
+            None => collect_cleanable(child, &child_path, rules, found),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filesystem::MemoryFileSystem;
+
+    fn cleanable(entry: &Entry, rules: &[Rule]) -> Vec<(String, u64, Option<String>)> {
+        find_cleanable(entry, rules).into_iter()
+            .map(|found| (found.path.to_string_lossy().into_owned(), found.size, found.marker))
+            .collect()
+    }
+
+    #[test]
+    fn it_finds_directories_that_match_a_rule() {
+        let fs = MemoryFileSystem::new()
+            .file("root/rust/Cargo.toml", 1)
+            .file("root/rust/target/debug/app", 1000)
+            .file("root/docs/target/notes", 10)
+            .file("root/web/package.json", 1)
+            .file("root/web/node_modules/a/index.js", 300)
+            .file("root/web/node_modules/a/node_modules/b/index.js", 200)
+            .file("root/app/src/__pycache__/main.pyc", 50)
+            .file("root/.cache/pip/wheel", 70)
+            .file("root/cmake/CMakeLists.txt", 1)
+            .file("root/cmake/build/app.o", 20);
+        let entry = Entry::for_path_in(&fs, Path::new("root")).unwrap();
+
+        assert_eq!(cleanable(&entry, &default_rules()), vec![
+            ("rust/target".to_string(), 1000, Some("Cargo.toml".to_string())),
+            ("web/node_modules".to_string(), 500, None),
+            (".cache".to_string(), 70, None),
+            ("app/src/__pycache__".to_string(), 50, None),
+            ("cmake/build".to_string(), 20, Some("CMakeLists.txt".to_string())),
+        ]);
+
+        let rules = parse_rules("docs:").unwrap();
+        assert_eq!(cleanable(&entry, &rules), vec![("docs".to_string(), 10, None)]);
+    }
+
+    #[test]
+    fn it_parses_rules() {
+        let rules = parse_rules("dist:package.json,vendor/:composer.json | go.mod,.venv");
+        assert_eq!(rules.unwrap(), vec![
+            Rule { name: "dist".to_string(), markers: vec!["package.json".to_string()] },
+            Rule {
+                name: "vendor".to_string(),
+                markers: vec!["composer.json".to_string(), "go.mod".to_string()],
+            },
+            Rule { name: ".venv".to_string(), markers: vec![] },
+        ]);
+        assert_eq!(parse_rules("a,,b").unwrap_err(), "Invalid rule \"\"");
+        assert_eq!(parse_rules("a/b:c").unwrap_err(), "Invalid rule \"a/b:c\"");
+    }
+}
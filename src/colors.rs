@@ -0,0 +1,222 @@
+use std::env;
+use std::str::FromStr;
+
+use libc;
+
+const KILO: u64 = 1_000;
+const MEGA: u64 = 1_000_000;
+const GIGA: u64 = 1_000_000_000;
+
+// Used when LS_COLORS is not set; these match what GNU ls uses by default.
+const DEFAULT_DIRECTORY: &'static str = "01;34";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color choice \"{}\"", s)),
+        }
+    }
+}
+
+impl ColorChoice {
+    // NO_COLOR (https://no-color.org/) only changes the default; asking for colors explicitly
+    // still works.
+    fn enabled(&self, no_color: bool, is_tty: bool) -> bool {
+        match self {
+            &ColorChoice::Always => true,
+            &ColorChoice::Never => false,
+            &ColorChoice::Auto => !no_color && is_tty,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Palette {
+    enabled: bool,
+    directory: Option<String>,
+    file: Option<String>,
+    suffixes: Vec<(String, String)>,
+}
+
+impl Palette {
+    pub fn from_env(choice: &ColorChoice) -> Palette {
+        let no_color = env::var_os("NO_COLOR").map(|value| !value.is_empty()).unwrap_or(false);
+        let is_tty = unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 };
+
+        if choice.enabled(no_color, is_tty) {
+            Palette::from_ls_colors(&env::var("LS_COLORS").unwrap_or_default())
+        } else {
+            Palette::disabled()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn disabled() -> Palette {
+        Palette {
+            enabled: false,
+            directory: None,
+            file: None,
+            suffixes: Vec::new(),
+        }
+    }
+
+    // LS_COLORS is a colon-separated list of key=value pairs, where the values are SGR codes.
+    // Keys are either two-letter file types ("di", "fi") or globs on the file name ("*.tar").
+    pub fn from_ls_colors(value: &str) -> Palette {
+        let mut palette = Palette {
+            enabled: true,
+            directory: Some(DEFAULT_DIRECTORY.to_string()),
+            file: None,
+            suffixes: Vec::new(),
+        };
+
+        for pair in value.split(':') {
+            let mut parts = pair.splitn(2, '=');
+            let (key, code) = match (parts.next(), parts.next()) {
+                (Some(key), Some(code)) if !code.is_empty() => (key, code.to_string()),
+                _ => continue,
+            };
+
+            match key {
+                "di" => palette.directory = Some(code),
+                "fi" => palette.file = Some(code),
+                _ if key.starts_with("*") => {
+                    palette.suffixes.push((key[1..].to_lowercase(), code));
+                },
+                _ => {},
+            }
+        }
+
+        palette
+    }
+
+    pub fn paint_name(&self, name: &str, is_file: bool) -> String {
+        if !self.enabled {
+            return name.to_string();
+        }
+
+        let code = if is_file {
+            self.suffix_code(name).or(self.file.as_ref())
+        } else {
+            self.directory.as_ref()
+        };
+
+        match code {
+            Some(code) => paint(name, code),
+            None => name.to_string(),
+        }
+    }
+
+    // Sizes are colored by their magnitude so the largest entries stand out.
+    pub fn paint_size(&self, size: u64, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+
+        let code = if size >= GIGA {
+            "01;31"
+        } else if size >= MEGA {
+            "33"
+        } else if size >= KILO {
+            "32"
+        } else {
+            return text.to_string();
+        };
+
+        paint(text, code)
+    }
+
+    // The longest matching suffix wins, so "*.tar.gz" takes precedence over "*.gz".
+    fn suffix_code(&self, name: &str) -> Option<&String> {
+        let name = name.to_lowercase();
+        self.suffixes.iter()
+            .filter(|&&(ref suffix, _)| name.ends_with(suffix.as_str()))
+            .max_by_key(|&&(ref suffix, _)| suffix.len())
+            .map(|&(_, ref code)| code)
+    }
+}
+
+fn paint(text: &str, code: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_color_choices() {
+        assert_eq!("auto".parse::<ColorChoice>(), Ok(ColorChoice::Auto));
+        assert_eq!("always".parse::<ColorChoice>(), Ok(ColorChoice::Always));
+        assert_eq!("never".parse::<ColorChoice>(), Ok(ColorChoice::Never));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    fn it_only_colors_terminals_automatically_without_no_color() {
+        assert_eq!(ColorChoice::Auto.enabled(false, true), true);
+        assert_eq!(ColorChoice::Auto.enabled(false, false), false);
+        assert_eq!(ColorChoice::Auto.enabled(true, true), false);
+        assert_eq!(ColorChoice::Always.enabled(true, false), true);
+        assert_eq!(ColorChoice::Never.enabled(false, true), false);
+    }
+
+    #[test]
+    fn it_colors_names_using_ls_colors() {
+        let palette = Palette::from_ls_colors("di=01;36:fi=00:*.gz=01;31:*.tar.gz=01;35:*.MP4=35");
+
+        assert_eq!(palette.paint_name("src/", false), "\x1b[01;36msrc/\x1b[0m");
+        assert_eq!(palette.paint_name("a.gz", true), "\x1b[01;31ma.gz\x1b[0m");
+        assert_eq!(palette.paint_name("a.tar.gz", true), "\x1b[01;35ma.tar.gz\x1b[0m");
+        assert_eq!(palette.paint_name("movie.mp4", true), "\x1b[35mmovie.mp4\x1b[0m");
+        assert_eq!(palette.paint_name("README", true), "\x1b[00mREADME\x1b[0m");
+    }
+
+    #[test]
+    fn it_colors_directories_by_default() {
+        let palette = Palette::from_ls_colors("");
+
+        assert_eq!(palette.paint_name("src/", false), "\x1b[01;34msrc/\x1b[0m");
+        assert_eq!(palette.paint_name("README", true), "README");
+    }
+
+    #[test]
+    fn it_ignores_broken_ls_colors_entries() {
+        let palette = Palette::from_ls_colors("garbage:di=:*.rs=32:=1");
+        assert_eq!(palette.paint_name("main.rs", true), "\x1b[32mmain.rs\x1b[0m");
+        assert_eq!(palette.paint_name("src/", false), "\x1b[01;34msrc/\x1b[0m");
+    }
+
+    #[test]
+    fn it_colors_sizes_by_magnitude() {
+        let palette = Palette::from_ls_colors("");
+
+        assert_eq!(palette.paint_size(10, "10 B"), "10 B");
+        assert_eq!(palette.paint_size(2_000, "2 kB"), "\x1b[32m2 kB\x1b[0m");
+        assert_eq!(palette.paint_size(2_000_000, "2 MB"), "\x1b[33m2 MB\x1b[0m");
+        assert_eq!(palette.paint_size(2_000_000_000, "2 GB"), "\x1b[01;31m2 GB\x1b[0m");
+    }
+
+    #[test]
+    fn it_does_nothing_when_disabled() {
+        let palette = Palette::disabled();
+
+        assert_eq!(palette.paint_name("src/", false), "src/");
+        assert_eq!(palette.paint_size(2_000_000_000, "2 GB"), "2 GB");
+    }
+}
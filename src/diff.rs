@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+use options::Options;
+use entry::Entry;
+use modes::DisplayableEntry;
+use root::Root;
+use utils::{self, SizeDisplay};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Resized,
+}
+
+// How an entry changed between two scans. Children are sorted by how much they changed, in either
+// direction.
+#[derive(Debug)]
+pub struct Delta {
+    pub name: String,
+    pub change: Change,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub children: Vec<Delta>,
+}
+
+impl Delta {
+    pub fn difference(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+
+    pub fn magnitude(&self) -> u64 {
+        self.new_size.abs_diff(self.old_size)
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.name.starts_with('.')
+    }
+}
+
+// Entries are matched by their path, which means by their name among their siblings. At least one
+// of the entries has to be given.
+pub fn compare(old: Option<&Entry>, new: Option<&Entry>) -> Delta {
+    let (name, change) = match (old, new) {
+        (None, Some(new)) => (new.name().clone(), Change::Added),
+        (Some(old), None) => (old.name().clone(), Change::Removed),
+        (Some(_), Some(new)) => (new.name().clone(), Change::Resized),
+        (None, None) => panic!("Nothing to compare"),
+    };
+
+    let old_children: Vec<&Entry> = old.map(|old| old.children_iter().collect())
+        .unwrap_or_default();
+    let new_children: Vec<&Entry> = new.map(|new| new.children_iter().collect())
+        .unwrap_or_default();
+    let old_by_name: HashMap<&String, &Entry> = old_children.iter()
+        .map(|child| (child.name(), *child))
+        .collect();
+    let new_names: HashSet<&String> = new_children.iter().map(|child| child.name()).collect();
+
+    let mut children: Vec<Delta> = new_children.iter()
+        .map(|child| compare(old_by_name.get(child.name()).cloned(), Some(child)))
+        .chain(old_children.iter()
+               .filter(|child| !new_names.contains(&child.name()))
+               .map(|child| compare(Some(child), None)))
+        .collect();
+    children.sort_by(|a, b| b.magnitude().cmp(&a.magnitude()).then_with(|| a.name.cmp(&b.name)));
+
+    Delta {
+        name: name,
+        change: change,
+        old_size: old.map(|old| old.size()).unwrap_or(0),
+        new_size: new.map(|new| new.size()).unwrap_or(0),
+        children: children,
+    }
+}
+
+// Roots are paired by name. When there is only one root on each side they are compared even if
+// their names differ, so a snapshot can be compared with a copy somewhere else.
+pub fn write_changes<W: Write>(snapshot: &Path,
+                               old_roots: Vec<Root>,
+                               new_roots: Vec<Root>,
+                               options: &Options,
+                               out: &mut W) -> io::Result<()> {
+    let only_one = old_roots.len() == 1 && new_roots.len() == 1;
+
+    for new_root in new_roots.iter() {
+        let old_root = old_roots.iter()
+            .find(|old_root| is_same_root(old_root, new_root))
+            .or(if only_one { old_roots.first() } else { None });
+
+        match old_root {
+            Some(old_root) => {
+                for line in delta_lines(old_root, new_root, options) {
+                    writeln!(out, "{}", line)?;
+                }
+            },
+            None => writeln!(out, "{}: Not in the snapshot {}",
+                             new_root.name(), snapshot.to_string_lossy())?,
+        }
+    }
+    Ok(())
+}
+
+// Snapshots have the full path of their roots, while a scan has the path it was given.
+fn is_same_root(old_root: &Root, new_root: &Root) -> bool {
+    old_root.name() == new_root.name() || *old_root.name() == utils::canonical_name(new_root.name())
+}
+
+fn delta_lines(old_root: &Root, new_root: &Root, options: &Options) -> Vec<String> {
+    let mut delta = compare(Some(old_root.entry()), Some(new_root.entry()));
+    delta.name = new_root.name().clone();
+
+    let format = options.size_format();
+    let mut lines = vec![format!(
+        "{} {} ({} -> {})",
+        delta.name,
+        signed_size(delta.difference(), options),
+        delta.old_size.as_size_display(format),
+        delta.new_size.as_size_display(format)
+    )];
+    add_child_lines(&delta, options, 0, "  ", &mut lines);
+    lines
+}
+
+// The limit and the minimum size apply to how much entries changed, rather than their size.
+// Entries that did not change at all are left out.
+fn add_child_lines(delta: &Delta,
+                   options: &Options,
+                   level: usize,
+                   prefix: &str,
+                   lines: &mut Vec<String>) {
+    if !options.depth_accepts(level) {
+        return;
+    }
+
+    let children = delta.children.iter()
+        .filter(|child| options.should_show_hidden() || !child.is_hidden())
+        .filter(|child| child.magnitude() > 0 && options.is_large_enough(child.magnitude()));
+
+    for (index, child) in children.enumerate() {
+        if options.limit_reached(index) {
+            break;
+        }
+
+        let marker = match child.change {
+            Change::Added => " (added)",
+            Change::Removed => " (removed)",
+            Change::Resized => "",
+        };
+        let size = signed_size(child.difference(), options);
+        lines.push(format!("{}{} {}{}", prefix, child.name, size, marker));
+        add_child_lines(child, options, level + 1, &format!("{}  ", prefix), lines);
+    }
+}
+
+pub fn signed_size(difference: i64, options: &Options) -> String {
+    let sign = if difference < 0 { "-" } else { "+" };
+    format!("{}{}", sign, difference.unsigned_abs().as_size_display(options.size_format()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use options::{Depth, Limit};
+    use std::fs;
+    use utils::tests::{self, TempDir};
+
+    #[test]
+    fn it_shows_what_grew_and_shrank() {
+        let base = TempDir::new("diff");
+        fs::create_dir_all(base.join("logs")).unwrap();
+        fs::write(base.join("logs/app.log"), vec![0u8; 1000]).unwrap();
+        fs::write(base.join("logs/old.log"), vec![0u8; 300]).unwrap();
+        fs::write(base.join("same"), vec![0u8; 10]).unwrap();
+        let old = Root::for_path(base.path()).unwrap();
+
+        fs::write(base.join("logs/app.log"), vec![0u8; 5000]).unwrap();
+        fs::remove_file(base.join("logs/old.log")).unwrap();
+        fs::write(base.join("new"), vec![0u8; 2000]).unwrap();
+        let new = Root::for_path(base.path()).unwrap();
+
+        let options = Options::builder()
+            .size_format(tests::bytes())
+            .depth(Depth::Limited(2))
+            .limit(Limit::Unlimited)
+            .build();
+        let lines = delta_lines(&old, &new, &options);
+        assert!(lines[0].starts_with(&format!("{} +", new.name())));
+        assert_eq!(&lines[1..], &[
+            "  logs/ +3700 B",
+            "    app.log +4000 B",
+            "    old.log -300 B (removed)",
+            "  new +2000 B (added)",
+        ]);
+
+        let options = Options::builder()
+            .size_format(tests::bytes())
+            .depth(Depth::Limited(2))
+            .min_size(2001)
+            .build();
+        let lines = delta_lines(&old, &new, &options);
+        assert_eq!(&lines[1..], &["  logs/ +3700 B", "    app.log +4000 B"]);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use options::Options;
+use entry::Entry;
+use filesystem::FileSystem;
+use modes::DisplayableEntry;
+use root::Root;
+
+// Only this much of every file is read to rule out most files that are not duplicates.
+const PARTIAL_SIZE: usize = 4096;
+const BUFFER_SIZE: usize = 64 * 1024;
+
+// Files with the same contents. The paths are relative to the root.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateSet {
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    // Every copy but one is a waste of room.
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+}
+
+// Files are first grouped by size, and only files of the same size are read. They are compared by
+// a hash of their first few kilobytes, and the ones that are still alike by a hash of everything.
+// Different files can have the same hash, so the files that are left are compared byte by byte
+// before they are reported. Empty files are left out since they take up no room. The files are
+// read from the file system the root was scanned from.
+pub fn find_duplicates<F: FileSystem>(fs: &F, root: &Root, options: &Options) -> Vec<DuplicateSet> {
+    let mut candidates = Vec::new();
+    let mut inodes = HashSet::new();
+    collect_candidates(root.entry(), Path::new(""), options, &mut inodes, &mut candidates);
+
+    let base = Path::new(root.name());
+    let mut sets: Vec<DuplicateSet> = group_by(candidates, |candidate| Some(candidate.size))
+        .into_iter()
+        .flat_map(|group| {
+            group_by(group, |candidate| hash_file(fs, base, candidate, PARTIAL_SIZE))
+        })
+        .flat_map(|group| {
+            if group[0].size as usize <= PARTIAL_SIZE {
+                // The partial hash already covered all of the contents.
+                vec![group]
+            } else {
+                group_by(group, |candidate| hash_file(fs, base, candidate, usize::max_value()))
+            }
+        })
+        .flat_map(|group| split_identical(fs, base, group))
+        .map(|group| {
+            let mut paths: Vec<PathBuf> = group.iter().map(|c| c.path.clone()).collect();
+            paths.sort();
+            DuplicateSet { size: group[0].size, paths: paths }
+        })
+        .collect();
+
+    sets.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then_with(|| a.paths.cmp(&b.paths)));
+    sets
+}
+
+// Hard links to the same inode are only kept once, since they do not take up any more room. What
+// is in archives is left out, since it cannot be read to be compared.
+fn collect_candidates(entry: &Entry,
+                      path: &Path,
+                      options: &Options,
+                      inodes: &mut HashSet<(u64, u64)>,
+                      candidates: &mut Vec<Candidate>) {
+    for child in entry.children_iter() {
+        if (child.is_hidden() && !options.should_show_hidden()) || child.is_archive() {
+            continue;
+        }
+
+        let child_path = path.join(child.name().trim_end_matches('/'));
+        if !child.is_file() {
+            collect_candidates(child, &child_path, options, inodes, candidates);
+        } else if child.size() > 0 && options.is_large_enough(child.size()) &&
+                  inodes.insert(child.inode()) {
+            candidates.push(Candidate { path: child_path, size: child.size() });
+        }
+    }
+}
+
+// Splits the candidates by a key and returns the groups that have more than one candidate. A
+// candidate without a key (because it could not be read) is left out.
+fn group_by<F, K>(candidates: Vec<Candidate>, key: F) -> Vec<Vec<Candidate>>
+    where F: Fn(&Candidate) -> Option<K>,
+          K: Hash + Eq {
+    let mut groups: HashMap<K, Vec<Candidate>> = HashMap::new();
+    for candidate in candidates {
+        if let Some(key) = key(&candidate) {
+            groups.entry(key).or_insert_with(Vec::new).push(candidate);
+        }
+    }
+
+    groups.into_iter().map(|(_, group)| group).filter(|group| group.len() > 1).collect()
+}
+
+// Splits candidates with the same hash into the sets that really have the same contents, leaving
+// out the ones without a copy.
+fn split_identical<F: FileSystem>(fs: &F,
+                                  base: &Path,
+                                  candidates: Vec<Candidate>) -> Vec<Vec<Candidate>> {
+    let mut sets = Vec::new();
+    let mut rest = candidates;
+
+    while rest.len() > 1 {
+        let first = rest.remove(0);
+        let first_path = base.join(&first.path);
+        let (same, different): (Vec<Candidate>, Vec<Candidate>) = rest.into_iter()
+            .partition(|candidate| {
+                same_contents(fs, &first_path, &base.join(&candidate.path)).unwrap_or(false)
+            });
+
+        if !same.is_empty() {
+            let mut set = vec![first];
+            set.extend(same);
+            sets.push(set);
+        }
+        rest = different;
+    }
+
+    sets
+}
+
+fn same_contents<F: FileSystem>(fs: &F, a: &Path, b: &Path) -> io::Result<bool> {
+    let mut a = fs.open(a)?;
+    let mut b = fs.open(b)?;
+    let mut a_buffer = vec![0u8; BUFFER_SIZE];
+    let mut b_buffer = vec![0u8; BUFFER_SIZE];
+
+    loop {
+        let read = read_up_to(&mut a, &mut a_buffer)?;
+        if read != read_up_to(&mut b, &mut b_buffer)? || a_buffer[..read] != b_buffer[..read] {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+// Reads until the buffer is full or the end is reached, since a single read can return less.
+fn read_up_to<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match reader.read(&mut buffer[total..])? {
+            0 => break,
+            read => total += read,
+        }
+    }
+    Ok(total)
+}
+
+fn hash_file<F: FileSystem>(fs: &F,
+                             base: &Path,
+                             candidate: &Candidate,
+                             limit: usize) -> Option<u64> {
+    hash_contents(fs, &base.join(&candidate.path), limit).ok()
+}
+
+// Hashes at most limit bytes from the start of the file.
+fn hash_contents<F: FileSystem>(fs: &F, path: &Path, limit: usize) -> io::Result<u64> {
+    let mut file = fs.open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut remaining = limit;
+
+    while remaining > 0 {
+        let wanted = remaining.min(BUFFER_SIZE);
+        let read = file.read(&mut buffer[..wanted])?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+        remaining -= read;
+    }
+
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modes::Mode;
+    use filesystem::MemoryFileSystem;
+
+    #[test]
+    fn it_finds_duplicates_ordered_by_wasted_room() {
+        let big: Vec<u8> = (0..10_000).map(|index| (index % 251) as u8).collect();
+        let mut different = big.clone();
+        different[9_999] = 0;
+        let fs = MemoryFileSystem::new()
+            .contents("base/big", big.clone())
+            .contents("base/copies/big", big)
+            .contents("base/same-start", different)
+            .hard_link("base/hard-link", "base/big")
+            .contents("base/a", b"small".to_vec())
+            .contents("base/copies/b", b"small".to_vec())
+            .contents("base/copies/c", b"small".to_vec())
+            .contents("base/other", b"other".to_vec())
+            .file("base/empty", 0)
+            .file("base/copies/empty", 0);
+
+        let options = Options::builder().mode(Mode::Duplicates).build();
+        let root = Root::for_path_in(&fs, Path::new("base")).unwrap();
+        let sets = find_duplicates(&fs, &root, &options);
+
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].size, 10_000);
+        assert_eq!(sets[0].wasted(), 10_000);
+        assert_eq!(sets[0].paths.len(), 2);
+        assert!(sets[0].paths.contains(&PathBuf::from("copies/big")));
+        assert_eq!(sets[1], DuplicateSet {
+            size: 5,
+            paths: vec![
+                PathBuf::from("a"),
+                PathBuf::from("copies/b"),
+                PathBuf::from("copies/c"),
+            ],
+        });
+    }
+
+    #[test]
+    fn it_compares_files_with_the_same_hash_byte_by_byte() {
+        let fs = MemoryFileSystem::new()
+            .contents("base/a", b"same".to_vec())
+            .contents("base/b", b"diff".to_vec())
+            .contents("base/c", b"same".to_vec())
+            .contents("base/d", b"else".to_vec());
+        let candidates = ["a", "b", "c", "d"].iter()
+            .map(|name| Candidate { path: PathBuf::from(name), size: 4 })
+            .collect();
+
+        let sets = split_identical(&fs, Path::new("base"), candidates);
+        let paths: Vec<Vec<PathBuf>> = sets.iter()
+            .map(|set| set.iter().map(|candidate| candidate.path.clone()).collect())
+            .collect();
+        assert_eq!(paths, vec![vec![PathBuf::from("a"), PathBuf::from("c")]]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use options::Options;
+use entry::Entry;
+use modes::DisplayableEntry;
+use root::Root;
+
+// Empty entries that are directly inside the same directory. The path of the parent is relative
+// to the root, so it is empty for the root itself.
+#[derive(Debug)]
+pub struct EmptyGroup<'a> {
+    pub parent: PathBuf,
+    pub parent_entry: &'a Entry,
+    pub entries: Vec<&'a Entry>,
+}
+
+impl<'a> EmptyGroup<'a> {
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries.iter().map(|entry| self.parent.join(entry.name())).collect()
+    }
+}
+
+// Finds directories without any files in them, and files without any contents. Directories that
+// only have empty directories in them are reported by themselves instead of everything inside.
+// Directories that could not be read are never empty, since what is in them is not known.
+// Archives are files on disk, so they are never empty directories even when nothing is in them,
+// and they are not looked into since what is in them cannot be removed by itself.
+pub fn find_empty<'a>(root: &'a Root, options: &Options) -> Vec<EmptyGroup<'a>> {
+    let mut groups = Vec::new();
+    add_empty(root.entry(), Path::new(""), options, &mut groups);
+    groups
+}
+
+fn add_empty<'a>(entry: &'a Entry,
+                 path: &Path,
+                 options: &Options,
+                 groups: &mut Vec<EmptyGroup<'a>>) {
+    let children: Vec<&Entry> = entry.children_iter()
+        .filter(|child| options.should_show_hidden() || !child.is_hidden())
+        .collect();
+
+    let empty: Vec<&Entry> = children.iter()
+        .filter(|child| if child.is_file() { child.size() == 0 } else { !has_contents(child) })
+        .cloned()
+        .collect();
+    if !empty.is_empty() {
+        groups.push(EmptyGroup { parent: path.to_path_buf(), parent_entry: entry, entries: empty });
+    }
+
+    for child in children.into_iter().filter(|child| {
+        !child.is_file() && !child.is_archive() && has_contents(child)
+    }) {
+        add_empty(child, &path.join(child.name().trim_end_matches('/')), options, groups);
+    }
+}
+
+fn has_contents(entry: &Entry) -> bool {
+    entry.is_archive() || entry.is_unreadable() ||
+        entry.children_iter().any(|child| child.is_file() || has_contents(child))
+}
+
+// Counts the empty directories and files.
+pub fn count(groups: &[EmptyGroup]) -> (usize, usize) {
+    groups.iter().flat_map(|group| group.entries.iter()).fold((0, 0), |(dirs, files), entry| {
+        if entry.is_file() { (dirs, files + 1) } else { (dirs + 1, files) }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archives::tests::tar;
+    use modes::Mode;
+    use filesystem::MemoryFileSystem;
+
+    #[test]
+    fn it_finds_empty_directories_and_files_by_parent() {
+        let fs = MemoryFileSystem::new()
+            .directory("base/build/chain/of/empty")
+            .directory("base/build/tmp")
+            .directory("base/.hidden")
+            .file("base/build/output", 6)
+            .file("base/build/zero", 0)
+            .file("base/src/main.rs", 12)
+            .file("base/empty-file", 0)
+            .unreadable("base/private")
+            .unreadable("base/build/locked");
+
+        let options = Options::builder().mode(Mode::Empty).build();
+        let root = Root::for_path_in(&fs, Path::new("base")).unwrap();
+        let groups = find_empty(&root, &options);
+
+        let mut paths: Vec<Vec<PathBuf>> = groups.iter().map(|group| group.paths()).collect();
+        for group in paths.iter_mut() {
+            group.sort();
+        }
+        assert_eq!(paths, vec![
+            vec![PathBuf::from("empty-file")],
+            vec![
+                PathBuf::from("build/chain/"),
+                PathBuf::from("build/tmp/"),
+                PathBuf::from("build/zero"),
+            ],
+        ]);
+        assert_eq!(count(&groups), (2, 2));
+
+        let options = Options::builder().mode(Mode::Empty).show_hidden(true).build();
+        assert_eq!(count(&find_empty(&root, &options)), (3, 2));
+    }
+
+    #[test]
+    fn it_does_not_look_into_archives() {
+        let fs = MemoryFileSystem::new()
+            .contents("base/backup.tar", tar(&[("zero", b"")]))
+            .contents("base/nothing.tar", tar(&[]))
+            .contents("base/nested/only-directories.tar", tar(&[]));
+        let root = Root::scan_path(&fs, Path::new("base"), true).unwrap();
+
+        let options = Options::builder().mode(Mode::Empty).build();
+        assert_eq!(count(&find_empty(&root, &options)), (0, 0));
+    }
+}
@@ -1,103 +1,591 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fmt;
-use std::fs;
+use std::io::{self, Read, Write};
 use std::path::Path;
 use std::slice::Iter;
 
+use archives::{self, Directory};
+use filesystem::{FileSystem, Metadata, RealFileSystem};
 use modes::DisplayableEntry;
+use snapshot::{Decoder, Encoder};
 use utils;
-use utils::SizeDisplay;
+use utils::{SizeDisplay, SizeFormat};
+use walk::Walk;
 
-#[derive(Debug)]
+// Which of the times of an entry to look at.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Timestamp {
+    Modified,
+    Accessed,
+}
+
+#[derive(Debug, Clone)]
 pub struct Entry {
     name: String,
+    // Names that are not valid UTF-8 are shown with replacement characters, so the name on disk is
+    // kept for finding the entry again.
+    os_name: Option<OsString>,
     self_size: u64,
     children: Vec<Entry>,
     is_file: bool,
+    uid: u32,
+    gid: u32,
+    modified: i64,
+    accessed: i64,
+    device: u64,
+    inode: u64,
+    // Archives that are looked into take up their size on disk, while what is in them has the
+    // size it has when unpacked and how much of the archive it takes up.
+    is_archive: bool,
+    compressed_size: Option<u64>,
+    // Directories that could not be read have no children, but are not known to be empty.
+    is_unreadable: bool,
 }
 
 impl Entry {
     pub fn for_path(path: &Path) -> Result<Entry, String> {
-        match fs::metadata(path) {
-            Ok(metadata) => Entry::from_metadata(path, &metadata),
+        Entry::for_path_in(&RealFileSystem, path)
+    }
+
+    pub fn for_path_in<F: FileSystem>(fs: &F, path: &Path) -> Result<Entry, String> {
+        Entry::scan_path(fs, path, false)
+    }
+
+    // Like for_path_in, but archives can be looked into like directories.
+    pub fn scan_path<F: FileSystem>(fs: &F,
+                                    path: &Path,
+                                    into_archives: bool) -> Result<Entry, String> {
+        match fs.metadata(path) {
+            Ok(metadata) => Entry::from_metadata(fs, path, &metadata, into_archives),
             Err(error) => Err(utils::describe_io_error(error))
         }
     }
 
-    pub fn from_metadata(path: &Path, metadata: &fs::Metadata) -> Result<Entry, String> {
+    pub fn from_metadata<F: FileSystem>(fs: &F,
+                                        path: &Path,
+                                        metadata: &Metadata,
+                                        into_archives: bool) -> Result<Entry, String> {
+        Entry::scan(fs, path, metadata, None, into_archives)
+    }
+
+    // Like scan_path, but reuses an earlier scan of the same path that was made at scanned_at.
+    // Directories that still have the same inode and modification time have the same children, so
+    // they are not read again. Every child is still looked at, since a file can grow without
+    // changing the directory it is in.
+    pub fn rescan<F: FileSystem>(fs: &F,
+                                 path: &Path,
+                                 previous: Entry,
+                                 scanned_at: i64,
+                                 into_archives: bool) -> Result<Entry, String> {
+        match fs.metadata(path) {
+            Ok(metadata) => {
+                let previous = Some((previous, scanned_at));
+                Entry::scan(fs, path, &metadata, previous, into_archives)
+            },
+            Err(error) => Err(utils::describe_io_error(error))
+        }
+    }
+
+    fn scan<F: FileSystem>(fs: &F,
+                           path: &Path,
+                           metadata: &Metadata,
+                           previous: Option<(Entry, i64)>,
+                           into_archives: bool) -> Result<Entry, String> {
+        let mut is_archive = false;
+        let mut is_unreadable = false;
         let mut children = if metadata.is_dir() {
-            Entry::in_directory(path)
+            let children = match previous {
+                Some((previous, scanned_at)) if !previous.is_file => {
+                    // A directory changed in the same second as it was scanned could have changed
+                    // after the scan without its modification time showing it. Permissions can
+                    // change without it too, so directories that could not be read are always
+                    // read again.
+                    let is_unchanged = !previous.is_unreadable &&
+                        previous.inode() == (metadata.device, metadata.inode) &&
+                        previous.modified == metadata.modified && previous.modified < scanned_at;
+                    let children = previous.children;
+                    if is_unchanged {
+                        let children = Entry::in_unchanged_directory(fs,
+                                                                     path,
+                                                                     children,
+                                                                     scanned_at,
+                                                                     into_archives);
+                        Some(children)
+                    } else {
+                        Entry::in_directory(fs, path, children, scanned_at, into_archives)
+                    }
+                },
+                _ => Entry::in_directory(fs, path, Vec::new(), 0, into_archives),
+            };
+            is_unreadable = children.is_none();
+            children.unwrap_or_default()
+        } else if metadata.is_file() && into_archives && archives::is_archive(path) {
+            match Entry::in_archive(fs, path, metadata, previous) {
+                Some(children) => {
+                    is_archive = true;
+                    children
+                },
+                None => vec![],
+            }
         } else if metadata.is_file() {
             vec![]
         } else {
             return Err("not a file or directory".to_string());
         };
 
-        children.sort_by(
-            // Note: We change the ordering to get in descending order
-            |a, b| b.size().cmp(&a.size())
-        );
+        sort_by_size(&mut children);
+
+        Ok(Entry {
+            name: utils::short_name_from_path(path, metadata.is_dir() || is_archive),
+            os_name: utils::non_utf8_name(path.file_name().unwrap_or(path.as_os_str())),
+            children: children,
+            self_size: metadata.size,
+            is_file: metadata.is_file() && !is_archive,
+            uid: metadata.uid,
+            gid: metadata.gid,
+            modified: metadata.modified,
+            accessed: metadata.accessed,
+            device: metadata.device,
+            inode: metadata.inode,
+            is_archive: is_archive,
+            compressed_size: None,
+            is_unreadable: is_unreadable,
+        })
+    }
+
+    // Archives that cannot be read are left as files. Like directories, archives that did not
+    // change since the last scan are not read again.
+    fn in_archive<F: FileSystem>(fs: &F,
+                                 path: &Path,
+                                 metadata: &Metadata,
+                                 previous: Option<(Entry, i64)>) -> Option<Vec<Entry>> {
+        if let Some((previous, scanned_at)) = previous {
+            let is_unchanged = previous.is_archive &&
+                previous.inode() == (metadata.device, metadata.inode) &&
+                previous.self_size == metadata.size &&
+                previous.modified == metadata.modified && previous.modified < scanned_at;
+            if is_unchanged {
+                return Some(previous.children);
+            }
+        }
+
+        let directory = fs.open(path).and_then(|file| archives::read(path, file)).ok()?;
+        Some(Entry::in_archived_directory(directory, metadata))
+    }
+
+    // What is in an archive has the owner and times of the archive, and no inode of its own.
+    fn in_archived_directory(directory: Directory, archive: &Metadata) -> Vec<Entry> {
+        let files = directory.files.into_iter().map(|member| {
+            let name = utils::short_name_from_path(Path::new(&member.path), false);
+            Entry::archived(name, member.size, member.compressed_size, Vec::new(), archive)
+        });
+        let directories = directory.directories.into_iter().map(|(name, directory)| {
+            let mut children = Entry::in_archived_directory(directory, archive);
+            sort_by_size(&mut children);
+            let compressed_size = children.iter()
+                .map(|child| child.compressed_size.unwrap_or(0))
+                .fold(0, |a, n| a + n);
+            Entry::archived(format!("{}/", name), 0, compressed_size, children, archive)
+        });
+        files.chain(directories).collect()
+    }
+
+    fn archived(name: String,
+                size: u64,
+                compressed_size: u64,
+                children: Vec<Entry>,
+                archive: &Metadata) -> Entry {
+        Entry {
+            is_file: !name.ends_with('/'),
+            name: name,
+            os_name: None,
+            self_size: size,
+            children: children,
+            uid: archive.uid,
+            gid: archive.gid,
+            modified: archive.modified,
+            accessed: archive.accessed,
+            device: archive.device,
+            inode: 0,
+            is_archive: false,
+            compressed_size: Some(compressed_size),
+            is_unreadable: false,
+        }
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    // Identifies the file on disk; hard links to the same file have the same inode.
+    pub fn inode(&self) -> (u64, u64) {
+        (self.device, self.inode)
+    }
+
+    // The name on disk, without the slash of directories. It differs from the name that is shown
+    // when it is not valid UTF-8.
+    pub fn file_name(&self) -> OsString {
+        match self.os_name {
+            Some(ref name) => name.clone(),
+            None => OsString::from(self.name.trim_end_matches('/')),
+        }
+    }
+
+    pub fn is_archive(&self) -> bool {
+        self.is_archive
+    }
+
+    // Whether this is something in an archive, which is not on disk by itself.
+    pub fn is_in_archive(&self) -> bool {
+        self.compressed_size.is_some()
+    }
+
+    // Whether this is a directory that could not be read, so what is in it is unknown.
+    pub fn is_unreadable(&self) -> bool {
+        self.is_unreadable
+    }
+
+    // Seconds since the Unix epoch.
+    pub fn time(&self, timestamp: Timestamp) -> i64 {
+        match timestamp {
+            Timestamp::Modified => self.modified,
+            Timestamp::Accessed => self.accessed,
+        }
+    }
+
+    // Age in seconds. Entries from the future are as new as they can be.
+    pub fn age(&self, timestamp: Timestamp, now: i64) -> u64 {
+        cmp::max(now - self.time(timestamp), 0) as u64
+    }
+
+    // Keeps the files that match, and the directories that lead to them. Directories only count
+    // their own size when they match themselves, so the sizes add up to what matched. Archives are
+    // kept or left out as a whole, like files.
+    pub fn filtered<F>(self, keep: &F) -> Option<Entry> where F: Fn(&Entry) -> bool {
+        let matches = keep(&self);
+        if self.is_file || self.is_archive {
+            return if matches { Some(self) } else { None };
+        }
+
+        let mut children: Vec<Entry> = self.children.into_iter()
+            .filter_map(|child| child.filtered(keep))
+            .collect();
+        if children.is_empty() && !matches {
+            return None;
+        }
+        sort_by_size(&mut children);
+
+        Some(Entry {
+            children: children,
+            self_size: if matches { self.self_size } else { 0 },
+            ..self
+        })
+    }
+
+    pub fn child_mut(&mut self, index: usize) -> Option<&mut Entry> {
+        self.children.get_mut(index)
+    }
+
+    // Replaces a child with a new scan of it. The children are sorted again since the size might
+    // have changed.
+    pub fn replace_child(&mut self, index: usize, child: Entry) {
+        self.children[index] = child;
+        sort_by_size(&mut self.children);
+    }
+
+    pub fn remove_child(&mut self, index: usize) -> Entry {
+        self.children.remove(index)
+    }
+
+    pub fn encode<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_str(&self.name)?;
+        encoder.write_u64(self.self_size)?;
+        encoder.write_bool(self.is_file)?;
+        encoder.write_u64(self.uid as u64)?;
+        encoder.write_u64(self.gid as u64)?;
+        encoder.write_i64(self.modified)?;
+        encoder.write_i64(self.accessed)?;
+        encoder.write_u64(self.device)?;
+        encoder.write_u64(self.inode)?;
+        encoder.write_bool(self.is_archive)?;
+        encoder.write_bool(self.compressed_size.is_some())?;
+        encoder.write_u64(self.compressed_size.unwrap_or(0))?;
+        encoder.write_bool(self.is_unreadable)?;
+        encoder.write_u64(self.children.len() as u64)?;
+        for child in self.children.iter() {
+            child.encode(encoder)?;
+        }
+        Ok(())
+    }
+
+    // Children are stored in the order they were sorted in when they were scanned.
+    pub fn decode<R: Read>(decoder: &mut Decoder<R>) -> Result<Entry, String> {
+        let name = decoder.read_string()?;
+        let self_size = decoder.read_u64()?;
+        let is_file = decoder.read_bool()?;
+        let uid = decoder.read_u32()?;
+        let gid = decoder.read_u32()?;
+        let modified = decoder.read_i64()?;
+        let accessed = decoder.read_i64()?;
+        let device = decoder.read_u64()?;
+        let inode = decoder.read_u64()?;
+        let is_archive = decoder.read_bool()?;
+        let has_compressed_size = decoder.read_bool()?;
+        let compressed_size = decoder.read_u64()?;
+        let is_unreadable = decoder.read_bool()?;
+
+        let count = decoder.read_u64()?;
+        let mut children = Vec::new();
+        for _ in 0..count {
+            children.push(Entry::decode(decoder)?);
+        }
 
         Ok(Entry {
-            name: utils::short_name_from_path(path, metadata.is_dir()),
+            name: name,
+            os_name: None,
+            self_size: self_size,
             children: children,
-            self_size: metadata.len(),
-            is_file: metadata.is_file(),
+            is_file: is_file,
+            uid: uid,
+            gid: gid,
+            modified: modified,
+            accessed: accessed,
+            device: device,
+            inode: inode,
+            is_archive: is_archive,
+            compressed_size: if has_compressed_size { Some(compressed_size) } else { None },
+            is_unreadable: is_unreadable,
         })
     }
 
-    fn in_directory(dir: &Path) -> Vec<Entry> {
-        match fs::read_dir(dir) {
-            Ok(read_dir) => {
-                read_dir.filter_map(|child| {
-                    match child {
-                        // TODO: Don't just ignore errors here; we should print them to STDERR and
-                        // *then* ignore them.
-                        Ok(child) => Entry::for_path(&child.path()).ok(),
-                        Err(..) => None,
+    // Every entry below this one, and this one first.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk::new(self)
+    }
+
+    // The same entry when there is nothing in it.
+    pub fn without_contents(&self) -> Entry {
+        Entry {
+            name: self.name.clone(),
+            os_name: self.os_name.clone(),
+            self_size: 0,
+            children: Vec::new(),
+            is_file: self.is_file,
+            uid: self.uid,
+            gid: self.gid,
+            modified: self.modified,
+            accessed: self.accessed,
+            device: self.device,
+            inode: self.inode,
+            is_archive: self.is_archive,
+            compressed_size: self.compressed_size,
+            is_unreadable: self.is_unreadable,
+        }
+    }
+
+    // Children that were found by an earlier scan are scanned again with what was found then.
+    // Children that cannot be looked at are left out, and nothing is found in a directory that
+    // cannot be read.
+    fn in_directory<F: FileSystem>(fs: &F,
+                                   dir: &Path,
+                                   previous: Vec<Entry>,
+                                   scanned_at: i64,
+                                   into_archives: bool) -> Option<Vec<Entry>> {
+        let mut previous: HashMap<String, Entry> = previous.into_iter()
+            .map(|child| (child.name.trim_end_matches('/').to_string(), child))
+            .collect();
+
+        match fs.read_dir(dir) {
+            Ok(paths) => {
+                Some(paths.into_iter().filter_map(|path| {
+                    let name = path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    match previous.remove(&name) {
+                        Some(earlier) => {
+                            Entry::rescan(fs, &path, earlier, scanned_at, into_archives).ok()
+                        },
+                        None => Entry::scan_path(fs, &path, into_archives).ok(),
                     }
-                }).collect()
+                }).collect())
             },
-            Err(..) => Vec::new()
+            Err(..) => None
         }
     }
 
+    fn in_unchanged_directory<F: FileSystem>(fs: &F,
+                                             dir: &Path,
+                                             previous: Vec<Entry>,
+                                             scanned_at: i64,
+                                             into_archives: bool) -> Vec<Entry> {
+        previous.into_iter().filter_map(|child| {
+            let path = dir.join(child.name.trim_end_matches('/'));
+            Entry::rescan(fs, &path, child, scanned_at, into_archives).ok()
+        }).collect()
+    }
+
     fn descendent_size(&self) -> u64 {
         self.children.iter().map(|child| child.size()).fold(0, |a, n| a + n)
     }
 }
 
+fn sort_by_size(entries: &mut Vec<Entry>) {
+    entries.sort_by(
+        // Note: We change the ordering to get in descending order
+        |a, b| b.size().cmp(&a.size())
+    );
+}
+
 impl DisplayableEntry for Entry {
     type Child = Entry;
 
     fn size(&self) -> u64 {
-        self.self_size + self.descendent_size()
+        if self.is_archive {
+            self.self_size
+        } else {
+            self.self_size + self.descendent_size()
+        }
     }
 
     fn name(&self) -> &String {
         &self.name
     }
 
-    fn children_iter(&self) -> Iter<Entry> {
+    fn children_iter(&self) -> Iter<'_, Entry> {
         self.children.iter()
     }
 
     fn is_file(&self) -> bool {
         self.is_file
     }
+
+    fn compressed_size(&self) -> Option<u64> {
+        self.compressed_size
+    }
+
+    fn uncompressed_size(&self) -> Option<u64> {
+        if self.is_archive {
+            Some(self.descendent_size())
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for Entry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {}", self.name(), self.size().as_size_display())
+        let size = self.size().as_size_display(&SizeFormat::default());
+        write!(f, "{} {}", self.name(), size)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use archives::tests::tar;
+    use filesystem::MemoryFileSystem;
     use modes::DisplayableEntry;
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
     use std::path::Path;
 
+    fn sizes(entry: &Entry) -> Vec<(String, u64)> {
+        entry.children_iter().map(|child| (child.name().clone(), child.size())).collect()
+    }
+
+    #[test]
+    fn it_scans_a_file_system() {
+        let fs = MemoryFileSystem::new()
+            .file("root/a", 10)
+            .file("root/b/c", 30)
+            .file("root/b/d", 5)
+            .owner("root/a", 1000, 100)
+            .times("root/a", 60, 120);
+        let entry = Entry::for_path_in(&fs, Path::new("root")).unwrap();
+
+        assert_eq!(entry.name(), "root/");
+        assert_eq!(entry.size(), 45);
+        assert_eq!(sizes(&entry), vec![("b/".to_string(), 35), ("a".to_string(), 10)]);
+
+        let b = entry.children_iter().next().unwrap();
+        assert_eq!(sizes(b), vec![("c".to_string(), 30), ("d".to_string(), 5)]);
+
+        let a = entry.children_iter().nth(1).unwrap();
+        assert_eq!((a.uid(), a.gid()), (1000, 100));
+        assert_eq!((a.time(Timestamp::Modified), a.time(Timestamp::Accessed)), (60, 120));
+    }
+
+    #[test]
+    fn it_leaves_out_what_cannot_be_read() {
+        let fs = MemoryFileSystem::new()
+            .file("root/readable", 10)
+            .unreadable("root/locked")
+            .file("root/locked/inside", 100)
+            .inaccessible("root/secret", 1000)
+            .other("root/socket");
+        let entry = Entry::for_path_in(&fs, Path::new("root")).unwrap();
+
+        assert_eq!(sizes(&entry), vec![("readable".to_string(), 10), ("locked/".to_string(), 0)]);
+        let locked = entry.children_iter().nth(1).unwrap();
+        assert!(locked.is_unreadable() && !entry.is_unreadable());
+        assert_eq!(Entry::for_path_in(&fs, Path::new("root/secret")).unwrap_err(),
+                   "Permission denied");
+        assert_eq!(Entry::for_path_in(&fs, Path::new("root/socket")).unwrap_err(),
+                   "not a file or directory");
+    }
+
+    #[test]
+    fn it_looks_into_archives() {
+        let archive = tar(&[("docs/a.txt", &[b'a'; 600]), ("b", b"b")]);
+        let fs = MemoryFileSystem::new()
+            .file("root/other", 100)
+            .contents("root/backup.tar", archive)
+            .contents("root/broken.zip", b"not a zip file".to_vec());
+        let entry = Entry::scan_path(&fs, Path::new("root"), true).unwrap();
+
+        // The archive counts with its size on disk, but what is in it is shown unpacked.
+        assert_eq!(entry.size(), 3698);
+        assert_eq!(sizes(&entry), vec![
+            ("backup.tar/".to_string(), 3584),
+            ("other".to_string(), 100),
+            ("broken.zip".to_string(), 14),
+        ]);
+        let backup = entry.children_iter().next().unwrap();
+        assert!(backup.is_archive() && !backup.is_file() && !backup.is_in_archive());
+        assert!(backup.children_iter().all(|child| child.is_in_archive()));
+        assert_eq!(backup.uncompressed_size(), Some(601));
+        assert_eq!(sizes(backup), vec![("docs/".to_string(), 600), ("b".to_string(), 1)]);
+        let compressed: Vec<Option<u64>> = backup.children_iter()
+            .map(|child| child.compressed_size())
+            .collect();
+        assert_eq!(compressed, vec![Some(1536), Some(1024)]);
+
+        let entry = Entry::for_path_in(&fs, Path::new("root")).unwrap();
+        assert_eq!(sizes(&entry)[0], ("backup.tar".to_string(), 3584));
+        assert!(entry.children_iter().all(|child| child.is_file()));
+    }
+
+    #[test]
+    fn it_only_reads_archives_that_changed() {
+        let path = Path::new("backup.tar");
+        let first = MemoryFileSystem::new().contents(path, tar(&[("a", b"1")]));
+        let entry = Entry::scan_path(&first, path, true).unwrap();
+
+        let second = MemoryFileSystem::new().contents(path, tar(&[("b", b"2")]));
+        let entry = Entry::rescan(&second, path, entry, 100, true).unwrap();
+        assert_eq!(sizes(&entry), vec![("a".to_string(), 1)]);
+
+        let third = MemoryFileSystem::new().contents(path, tar(&[("b", b"2")])).times(path, 50, 50);
+        let entry = Entry::rescan(&third, path, entry, 100, true).unwrap();
+        assert_eq!(sizes(&entry), vec![("b".to_string(), 1)]);
+    }
+
     #[test]
     fn it_can_be_constructed_with_a_path() {
         let pwd = Entry::for_path(Path::new(".")).unwrap();
@@ -131,10 +619,62 @@ mod test {
 
     #[test]
     fn it_can_be_displayed() {
-        use utils::SizeDisplay;
+        use utils::{SizeDisplay, SizeFormat};
+
+        let file = Entry::for_path(Path::new("./LICENSE")).unwrap();
+        let size = file.size().as_size_display(&SizeFormat::default());
+        assert_eq!(format!("{}", file), format!("LICENSE {}", size));
+    }
+
+    #[test]
+    fn it_can_replace_and_remove_children() {
+        let mut entry = Entry::for_path(Path::new("./src")).unwrap();
+        let count = entry.children_iter().count();
+        let license = Entry::for_path(Path::new("./LICENSE")).unwrap();
+        let license_size = license.size();
 
+        entry.replace_child(0, license);
+        assert_eq!(entry.children_iter().count(), count);
+        assert!(entry.children_iter().any(|child| child.name() == "LICENSE"));
+
+        let index = entry.children_iter().position(|child| child.name() == "LICENSE").unwrap();
+        let removed = entry.remove_child(index);
+        assert_eq!(removed.size(), license_size);
+        assert_eq!(entry.children_iter().count(), count - 1);
+    }
+
+    #[test]
+    fn it_records_the_owner() {
+        let file = Entry::for_path(Path::new("./LICENSE")).unwrap();
+        let metadata = fs::metadata("./LICENSE").unwrap();
+
+        assert_eq!(file.uid(), metadata.uid());
+        assert_eq!(file.gid(), metadata.gid());
+    }
+
+    #[test]
+    fn it_records_times() {
         let file = Entry::for_path(Path::new("./LICENSE")).unwrap();
-        assert_eq!(format!("{}", file), format!("LICENSE {}", file.size().as_size_display()));
+        let metadata = fs::metadata("./LICENSE").unwrap();
+
+        assert_eq!(file.time(Timestamp::Modified), metadata.mtime());
+        assert_eq!(file.time(Timestamp::Accessed), metadata.atime());
+        assert_eq!(file.age(Timestamp::Modified, metadata.mtime() + 60), 60);
+        assert_eq!(file.age(Timestamp::Modified, metadata.mtime() - 60), 0);
+    }
+
+    #[test]
+    fn it_can_be_filtered() {
+        let entry = Entry::for_path(Path::new(".")).unwrap();
+        let filtered = entry.filtered(&|entry: &Entry| entry.name().ends_with(".rs")).unwrap();
+
+        let src = filtered.children_iter().find(|child| child.name() == "src/").unwrap();
+        assert_eq!(filtered.children_iter().count(), 1);
+        assert!(src.children_iter().all(|child| child.name().ends_with(".rs")));
+        assert_eq!(filtered.size(), src.children_iter().map(|child| child.size()).sum());
+
+        let license = Entry::for_path(Path::new("./LICENSE")).unwrap();
+        assert!(license.filtered(&|_: &Entry| false).is_none());
     }
 
     #[test]
@@ -0,0 +1,376 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    File,
+    Directory,
+    Other,
+}
+
+// What a scan needs to know about a file or directory. Times are seconds since the Unix epoch.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Metadata {
+    pub kind: Kind,
+    pub size: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub modified: i64,
+    pub accessed: i64,
+    pub device: u64,
+    pub inode: u64,
+}
+
+impl Metadata {
+    pub fn is_dir(&self) -> bool {
+        self.kind == Kind::Directory
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.kind == Kind::File
+    }
+}
+
+// Where scans find files and directories. Symbolic links are followed.
+pub trait FileSystem {
+    type File: Read + Seek;
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    // The paths of everything in the directory, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    // Files are only read to look into archives, to compare them and to read what git knows about
+    // them.
+    fn open(&self, path: &Path) -> io::Result<Self::File>;
+
+    // The full path from the root of the file system, without links or "..".
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    // Only the interactive mode deletes anything, and only when asked to. Like in std::fs, links
+    // are removed rather than what they point to.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+}
+
+// The file system of the machine.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    type File = File;
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = fs::metadata(path)?;
+        let kind = if metadata.is_dir() {
+            Kind::Directory
+        } else if metadata.is_file() {
+            Kind::File
+        } else {
+            Kind::Other
+        };
+
+        Ok(Metadata {
+            kind: kind,
+            size: metadata.len(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            modified: metadata.mtime(),
+            accessed: metadata.atime(),
+            device: metadata.dev(),
+            inode: metadata.ino(),
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        // TODO: Don't just ignore errors here; we should print them to STDERR and *then* ignore
+        // them.
+        Ok(fs::read_dir(path)?.filter_map(|child| child.ok()).map(|child| child.path()).collect())
+    }
+
+    fn open(&self, path: &Path) -> io::Result<File> {
+        File::open(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+}
+
+// A file system that only exists in memory, made up one entry at a time. Parent directories are
+// made as they are needed, and directories take up no room themselves:
+//
+//     let fs = MemoryFileSystem::new()
+//         .file("root/src/main.rs", 1200)
+//         .file("root/.git/HEAD", 20)
+//         .unreadable("root/secret");
+//
+// Everything belongs to user and group 0 and was last modified and accessed at time 0 unless
+// changed with owner and times. Files are full of zeros unless made with contents.
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    entries: HashMap<PathBuf, Metadata>,
+    contents: HashMap<PathBuf, Vec<u8>>,
+    unreadable: HashSet<PathBuf>,
+    inaccessible: HashSet<PathBuf>,
+    // What has been deleted, along with everything in it.
+    removed: RefCell<HashSet<PathBuf>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> MemoryFileSystem {
+        MemoryFileSystem::default()
+    }
+
+    pub fn directory<P: AsRef<Path>>(self, path: P) -> MemoryFileSystem {
+        self.add(path.as_ref(), Kind::Directory, 0)
+    }
+
+    pub fn file<P: AsRef<Path>>(self, path: P, size: u64) -> MemoryFileSystem {
+        self.add(path.as_ref(), Kind::File, size)
+    }
+
+    pub fn contents<P: AsRef<Path>>(mut self, path: P, contents: Vec<u8>) -> MemoryFileSystem {
+        self = self.file(path.as_ref(), contents.len() as u64);
+        self.contents.insert(path.as_ref().to_path_buf(), contents);
+        self
+    }
+
+    // Another name for a file that is already there, like a hard link. Both names have the same
+    // inode and contents.
+    pub fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(mut self,
+                                                     path: P,
+                                                     existing: Q) -> MemoryFileSystem {
+        let metadata = match self.entries.get(existing.as_ref()) {
+            Some(metadata) => metadata.clone(),
+            None => return self,
+        };
+        if let Some(parent) = path.as_ref().parent() {
+            self = self.directory(parent);
+        }
+
+        if let Some(contents) = self.contents.get(existing.as_ref()).cloned() {
+            self.contents.insert(path.as_ref().to_path_buf(), contents);
+        }
+        self.entries.insert(path.as_ref().to_path_buf(), metadata);
+        self
+    }
+
+    // Something that is neither a file nor a directory, like a socket.
+    pub fn other<P: AsRef<Path>>(self, path: P) -> MemoryFileSystem {
+        self.add(path.as_ref(), Kind::Other, 0)
+    }
+
+    pub fn owner<P: AsRef<Path>>(mut self, path: P, uid: u32, gid: u32) -> MemoryFileSystem {
+        if let Some(metadata) = self.entries.get_mut(path.as_ref()) {
+            metadata.uid = uid;
+            metadata.gid = gid;
+        }
+        self
+    }
+
+    pub fn times<P: AsRef<Path>>(mut self,
+                                 path: P,
+                                 modified: i64,
+                                 accessed: i64) -> MemoryFileSystem {
+        if let Some(metadata) = self.entries.get_mut(path.as_ref()) {
+            metadata.modified = modified;
+            metadata.accessed = accessed;
+        }
+        self
+    }
+
+    // A directory that can be seen but not read, like one without read permission.
+    pub fn unreadable<P: AsRef<Path>>(mut self, path: P) -> MemoryFileSystem {
+        self = self.directory(path.as_ref());
+        self.unreadable.insert(path.as_ref().to_path_buf());
+        self
+    }
+
+    // Something that cannot even be looked at, like what is in a directory without execute
+    // permission.
+    pub fn inaccessible<P: AsRef<Path>>(mut self, path: P, size: u64) -> MemoryFileSystem {
+        self = self.file(path.as_ref(), size);
+        self.inaccessible.insert(path.as_ref().to_path_buf());
+        self
+    }
+
+    fn is_removed(&self, path: &Path) -> bool {
+        let removed = self.removed.borrow();
+        path.ancestors().any(|ancestor| removed.contains(ancestor))
+    }
+
+    fn add(mut self, path: &Path, kind: Kind, size: u64) -> MemoryFileSystem {
+        if path.as_os_str().is_empty() || self.entries.contains_key(path) {
+            return self;
+        }
+        if let Some(parent) = path.parent() {
+            self = self.directory(parent);
+        }
+
+        let inode = self.entries.len() as u64 + 1;
+        self.entries.insert(path.to_path_buf(), Metadata {
+            kind: kind,
+            size: size,
+            uid: 0,
+            gid: 0,
+            modified: 0,
+            accessed: 0,
+            device: 1,
+            inode: inode,
+        });
+        self
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    type File = Cursor<Vec<u8>>;
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if self.inaccessible.contains(path) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "inaccessible"));
+        }
+        if self.is_removed(path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "removed"));
+        }
+        self.entries.get(path).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "not in the file system")
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.metadata(path)?.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::Other, "not a directory"));
+        }
+        if self.unreadable.contains(path) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "unreadable"));
+        }
+
+        let mut children: Vec<PathBuf> = self.entries.keys()
+            .filter(|child| child.parent() == Some(path) && !self.is_removed(child))
+            .cloned()
+            .collect();
+        children.sort();
+        Ok(children)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Cursor<Vec<u8>>> {
+        let metadata = self.metadata(path)?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(io::ErrorKind::Other, "not a file"));
+        }
+        let contents = self.contents.get(path).cloned()
+            .unwrap_or_else(|| vec![0; metadata.size as usize]);
+        Ok(Cursor::new(contents))
+    }
+
+    // There are no links, and paths are used as they were given.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.metadata(path).map(|_| path.to_path_buf())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        if self.metadata(path)?.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::Other, "is a directory"));
+        }
+        self.removed.borrow_mut().insert(path.to_path_buf());
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        if !self.metadata(path)?.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::Other, "not a directory"));
+        }
+        self.removed.borrow_mut().insert(path.to_path_buf());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_makes_parent_directories() {
+        let fs = MemoryFileSystem::new().file("root/a/b", 10).file("root/c", 5);
+
+        assert!(fs.metadata(Path::new("root/a")).unwrap().is_dir());
+        assert_eq!(fs.metadata(Path::new("root/a/b")).unwrap().size, 10);
+        assert_eq!(fs.read_dir(Path::new("root")).unwrap(),
+                   vec![PathBuf::from("root/a"), PathBuf::from("root/c")]);
+        assert!(fs.read_dir(Path::new("root/c")).is_err());
+    }
+
+    #[test]
+    fn it_reads_files() {
+        let fs = MemoryFileSystem::new().contents("root/a", b"text".to_vec()).file("root/b", 2);
+
+        let mut text = String::new();
+        fs.open(Path::new("root/a")).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "text");
+        assert_eq!(fs.metadata(Path::new("root/a")).unwrap().size, 4);
+        assert_eq!(fs.open(Path::new("root/b")).unwrap().into_inner(), vec![0, 0]);
+        assert!(fs.open(Path::new("root")).is_err());
+    }
+
+    #[test]
+    fn it_makes_hard_links() {
+        let fs = MemoryFileSystem::new()
+            .contents("root/a", b"text".to_vec())
+            .hard_link("b", "root/a");
+
+        let a = fs.metadata(Path::new("root/a")).unwrap();
+        assert_eq!(fs.metadata(Path::new("b")).unwrap(), a);
+        assert_eq!(fs.open(Path::new("b")).unwrap().into_inner(), b"text".to_vec());
+    }
+
+    #[test]
+    fn it_fails_like_a_file_system_without_permissions() {
+        let fs = MemoryFileSystem::new().unreadable("root/secret").inaccessible("root/hidden", 1);
+
+        let error = fs.read_dir(Path::new("root/secret")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert!(fs.metadata(Path::new("root/secret")).unwrap().is_dir());
+
+        let error = fs.metadata(Path::new("root/hidden")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        let error = fs.metadata(Path::new("root/missing")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn it_removes_files_and_directories() {
+        let fs = MemoryFileSystem::new().file("root/a/b", 10).file("root/c", 5);
+
+        assert!(fs.remove_file(Path::new("root/a")).is_err());
+        assert!(fs.remove_dir_all(Path::new("root/c")).is_err());
+
+        fs.remove_file(Path::new("root/c")).unwrap();
+        fs.remove_dir_all(Path::new("root/a")).unwrap();
+        assert!(fs.metadata(Path::new("root/a/b")).is_err());
+        assert_eq!(fs.read_dir(Path::new("root")).unwrap(), Vec::<PathBuf>::new());
+        assert!(fs.remove_file(Path::new("root/c")).is_err());
+    }
+
+    #[test]
+    fn it_reads_the_real_file_system() {
+        let metadata = RealFileSystem.metadata(Path::new("./LICENSE")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.size, fs::metadata("./LICENSE").unwrap().len());
+        assert!(RealFileSystem.read_dir(Path::new("./src")).unwrap()
+                .contains(&PathBuf::from("./src/filesystem.rs")));
+    }
+}
@@ -0,0 +1,89 @@
+use options::Options;
+use entry::{Entry, Timestamp};
+use root::Root;
+use utils;
+
+// Which files of a tree to keep. Nothing is left out until asked for, one restriction at a time:
+//
+//     let filter = Filter::new().owner(1000).older_than(30 * 24 * 60 * 60);
+#[derive(Debug, Clone)]
+pub struct Filter {
+    owner: Option<u32>,
+    older_than: Option<u64>,
+    newer_than: Option<u64>,
+    timestamp: Timestamp,
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter::new()
+    }
+}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter { owner: None, older_than: None, newer_than: None, timestamp: Timestamp::Modified }
+    }
+
+    // The filter asked for on the command line.
+    pub fn for_options(options: &Options) -> Filter {
+        Filter {
+            owner: options.owner(),
+            older_than: options.older_than(),
+            newer_than: options.newer_than(),
+            timestamp: options.timestamp(),
+        }
+    }
+
+    pub fn owner(mut self, uid: u32) -> Filter {
+        self.owner = Some(uid);
+        self
+    }
+
+    // Ages are in seconds, and measured by the timestamp of the filter.
+    pub fn older_than(mut self, age: u64) -> Filter {
+        self.older_than = Some(age);
+        self
+    }
+
+    pub fn newer_than(mut self, age: u64) -> Filter {
+        self.newer_than = Some(age);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Filter {
+        self.timestamp = timestamp;
+        self
+    }
+
+    // Restricts the tree to the files that were asked for, before it is shown in any way.
+    pub fn apply(&self, root: Root) -> Root {
+        if self.keeps_everything() {
+            return root;
+        }
+
+        let now = utils::now();
+        root.filtered(&|entry: &Entry| self.keeps(entry, now))
+    }
+
+    // Like apply, for a part of a tree. Nothing is left of it when nothing in it matches.
+    pub fn apply_to_entry(&self, entry: Entry) -> Option<Entry> {
+        if self.keeps_everything() {
+            return Some(entry);
+        }
+
+        let now = utils::now();
+        entry.filtered(&|entry: &Entry| self.keeps(entry, now))
+    }
+
+    fn keeps_everything(&self) -> bool {
+        self.owner.is_none() && self.older_than.is_none() && self.newer_than.is_none()
+    }
+
+    fn keeps(&self, entry: &Entry, now: i64) -> bool {
+        let age = entry.age(self.timestamp, now);
+        self.owner.map(|uid| entry.uid() == uid).unwrap_or(true) &&
+            self.older_than.map(|limit| age >= limit).unwrap_or(true) &&
+            self.newer_than.map(|limit| age < limit).unwrap_or(true)
+    }
+}
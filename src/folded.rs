@@ -0,0 +1,129 @@
+use std::io::{self, Write};
+
+use options::Options;
+use modes::DisplayableEntry;
+use root::Root;
+
+pub fn write_folded<W: Write>(root: &Root, options: &Options, out: &mut W) -> io::Result<()> {
+    for line in folded_lines(root, options.should_show_hidden()) {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+// Every entry that takes up room of its own becomes a single line with the names of all its
+// ancestors (including the root) joined by semicolons, followed by a space and the size in bytes.
+// This is the "folded stack" format that inferno and flamegraph.pl read. Directories have a line
+// for their own size, so the lines add up to the size of the root when nothing is hidden.
+fn folded_lines<T: DisplayableEntry>(root: &T, show_hidden: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    collect_lines(root, &mut Vec::new(), show_hidden, &mut lines);
+    lines
+}
+
+fn collect_lines<T: DisplayableEntry>(entry: &T,
+                                      stack: &mut Vec<String>,
+                                      show_hidden: bool,
+                                      lines: &mut Vec<String>) {
+    stack.push(frame_name(entry.name()));
+
+    // What is in an archive is counted unpacked, so it would not add up to the archive.
+    let is_archive = entry.uncompressed_size().is_some();
+    let children_size: u64 = if is_archive {
+        0
+    } else {
+        entry.children_iter().map(|child| child.size()).sum()
+    };
+    let own_size = entry.size().saturating_sub(children_size);
+    if own_size > 0 {
+        lines.push(format!("{} {}", stack.join(";"), own_size));
+    }
+
+    if !is_archive {
+        for child in entry.children_iter() {
+            if !show_hidden && child.is_hidden() {
+                continue;
+            }
+            collect_lines(child, stack, show_hidden, lines);
+        }
+    }
+
+    stack.pop();
+}
+
+fn frame_name(name: &str) -> String {
+    // Semicolons separate the frames, so they cannot appear inside of a name.
+    let name = name.replace(";", ",");
+
+    if name.len() > 1 && name.ends_with("/") {
+        name[..name.len() - 1].to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filesystem::MemoryFileSystem;
+    use root::Root;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn it_prints_one_line_per_file_with_the_full_stack() {
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let lines = folded_lines(&root, true);
+        let main_size = fs::metadata("./src/main.rs").unwrap().len();
+
+        assert!(lines.contains(&format!("./src;main.rs {}", main_size)));
+        assert!(lines.iter().all(|line| line.starts_with("./src")));
+    }
+
+    #[test]
+    fn it_prints_the_own_size_of_directories_so_the_lines_add_up() {
+        let root = Root::for_path(Path::new(".")).unwrap();
+        let total: u64 = folded_lines(&root, true).iter()
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+            .sum();
+
+        assert_eq!(total, root.size());
+    }
+
+    #[test]
+    fn it_leaves_out_entries_that_take_up_no_room() {
+        let fs = MemoryFileSystem::new()
+            .file("root/a/b", 10)
+            .file("root/empty.txt", 0)
+            .directory("root/nothing");
+        let root = Root::for_path_in(&fs, Path::new("root")).unwrap();
+
+        assert_eq!(folded_lines(&root, true), vec!["root;a;b 10".to_string()]);
+    }
+
+    #[test]
+    fn it_prints_a_single_line_for_file_roots() {
+        let root = Root::for_path(Path::new("./LICENSE")).unwrap();
+        let lines = folded_lines(&root, false);
+
+        assert_eq!(lines, vec![format!("./LICENSE {}", root.size())]);
+    }
+
+    #[test]
+    fn it_skips_hidden_entries_unless_asked_to_show_them() {
+        let root = Root::for_path(Path::new(".")).unwrap();
+
+        let without_hidden = folded_lines(&root, false);
+        let with_hidden = folded_lines(&root, true);
+
+        assert!(!without_hidden.iter().any(|line| line.starts_with(".;.gitignore ")));
+        assert!(with_hidden.iter().any(|line| line.starts_with(".;.gitignore ")));
+    }
+
+    #[test]
+    fn it_strips_trailing_slashes_and_semicolons_from_frame_names() {
+        assert_eq!(frame_name("src/"), "src");
+        assert_eq!(frame_name("/"), "/");
+        assert_eq!(frame_name("a;b"), "a,b");
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use options::Options;
+use filesystem::FileSystem;
+use folded;
+use html;
+use root::Root;
+use svg;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Folded,
+    Html,
+    Svg,
+}
+
+impl Format {
+    // Like Mode::work.
+    pub fn work<F: FileSystem>(&self, fs: &F, root: Root, options: &Options) {
+        match self {
+            &Format::Text => options.mode().work(fs, root, options),
+            _ => {
+                let stdout = io::stdout();
+                let _ = self.render(fs, root, options, &mut stdout.lock());
+            },
+        }
+    }
+
+    // Like Mode::render.
+    pub fn render<F: FileSystem, W: Write>(&self,
+                                           fs: &F,
+                                           root: Root,
+                                           options: &Options,
+                                           out: &mut W) -> io::Result<()> {
+        match self {
+            &Format::Text => options.mode().render(fs, root, options, out),
+            &Format::Folded => folded::write_folded(&root, options, out),
+            &Format::Html => html::write_html(&root, options, out),
+            &Format::Svg => svg::write_svg(&root, options, out),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "folded" => Ok(Format::Folded),
+            "html" => Ok(Format::Html),
+            "svg" => Ok(Format::Svg),
+            _ => Err(format!("Unknown format \"{}\"", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Format;
+
+    #[test]
+    fn it_parses_format_names() {
+        assert_eq!("text".parse::<Format>(), Ok(Format::Text));
+        assert_eq!("folded".parse::<Format>(), Ok(Format::Folded));
+        assert_eq!("html".parse::<Format>(), Ok(Format::Html));
+        assert_eq!("svg".parse::<Format>(), Ok(Format::Svg));
+    }
+
+    #[test]
+    fn it_rejects_unknown_format_names() {
+        assert_eq!("yaml".parse::<Format>(), Err("Unknown format \"yaml\"".to_string()));
+    }
+}
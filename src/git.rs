@@ -0,0 +1,631 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use breakdown::Group;
+use entry::Entry;
+use filesystem::FileSystem;
+use modes::DisplayableEntry;
+
+const INDEX_SIGNATURE: &'static [u8] = b"DIRC";
+const STATUSES: [Status; 5] = [
+    Status::Tracked,
+    Status::Untracked,
+    Status::Ignored,
+    Status::Repository,
+    Status::Outside,
+];
+
+// What a file is to the git work tree it is in. The repository itself is everything in .git.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Status {
+    Tracked,
+    Untracked,
+    Ignored,
+    Repository,
+    Outside,
+}
+
+impl Status {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Status::Tracked => "tracked",
+            Status::Untracked => "untracked",
+            Status::Ignored => "ignored",
+            Status::Repository => ".git",
+            Status::Outside => "not in git",
+        }
+    }
+
+    fn index(&self) -> usize {
+        STATUSES.iter().position(|status| status == self).unwrap_or(0)
+    }
+}
+
+// How much room the files of every status take up, and how many of them there are.
+#[derive(Debug, Default, Clone, Copy)]
+struct Split {
+    totals: [(u64, u64); 5],
+}
+
+impl Split {
+    fn add(&mut self, status: Status, size: u64) {
+        let total = &mut self.totals[status.index()];
+        total.0 += size;
+        total.1 += 1;
+    }
+
+    fn add_split(&mut self, other: &Split) {
+        for (total, other) in self.totals.iter_mut().zip(other.totals.iter()) {
+            total.0 += other.0;
+            total.1 += other.1;
+        }
+    }
+
+    fn groups(&self) -> Vec<Group> {
+        STATUSES.iter().zip(self.totals.iter())
+            .filter(|&(_, &(_, count))| count > 0)
+            .map(|(status, &(size, count))| {
+                Group { name: status.label().to_string(), size: size, count: count }
+            })
+            .collect()
+    }
+}
+
+// Finds the git work trees the entry at the path is in, and splits the files of the entry and of
+// every directory below it by their status. Directories are kept by their path relative to the
+// entry. Only the index and the ignore files are read, so files that were changed or deleted
+// since they were added are still tracked, and ignore rules from the configuration of the user
+// are not used.
+//
+// Archives are counted as files, since what is in them cannot be in git by itself.
+pub fn classify<F: FileSystem>(fs: &F,
+                               path: &Path,
+                               entry: &Entry) -> HashMap<PathBuf, Vec<Group>> {
+    let mut classifier = Classifier { fs: fs, rules: Vec::new(), splits: HashMap::new() };
+    let name = entry.name().trim_end_matches('/');
+    let place = classifier.enclosing_place(path);
+
+    if entry.is_file() || entry.is_archive() {
+        let mut split = Split::default();
+        split.add(place.status(name, &classifier.rules), entry.size());
+        classifier.splits.insert(PathBuf::new(), split);
+    } else {
+        let place = place.directory(name, &classifier.rules);
+        classifier.visit(path, PathBuf::new(), entry, place);
+    }
+
+    classifier.splits.into_iter().map(|(path, split)| (path, split.groups())).collect()
+}
+
+struct Classifier<'a, F: 'a + FileSystem> {
+    fs: &'a F,
+    rules: Vec<Rule>,
+    splits: HashMap<PathBuf, Split>,
+}
+
+impl<'a, F: FileSystem> Classifier<'a, F> {
+    // Where the parent of the path is, when it is in a work tree. The ignore files of the
+    // directories above the path are read like they would have been when scanning from the top of
+    // the work tree.
+    fn enclosing_place(&mut self, path: &Path) -> Place {
+        let top = path.ancestors().skip(1)
+            .find(|dir| self.fs.metadata(&dir.join(".git")).is_ok())
+            .map(Path::to_path_buf);
+        let top = match top {
+            Some(top) => top,
+            None => return Place::default(),
+        };
+
+        let mut place = self.enter_work_tree(&top);
+        let mut dir = top.clone();
+        let names: Vec<String> = path.strip_prefix(&top).unwrap_or(path).iter()
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        for name in names.iter().take(names.len().saturating_sub(1)) {
+            place = place.directory(name, &self.rules);
+            dir.push(name);
+            self.read_ignore_file(&dir.join(".gitignore"), &place.relative);
+        }
+        place
+    }
+
+    // Tracked paths are read from the index of the repository. A .git file points to a repository
+    // somewhere else, like for submodules and extra work trees.
+    fn enter_work_tree(&mut self, dir: &Path) -> Place {
+        let git = dir.join(".git");
+        let repository = match self.fs.metadata(&git) {
+            Ok(ref metadata) if metadata.is_file() => {
+                read_text(self.fs, &git)
+                    .and_then(|text| text.lines().next().map(str::to_string))
+                    .and_then(|line| {
+                        line.trim().strip_prefix("gitdir:").map(|path| join(dir, path.trim()))
+                    })
+                    .unwrap_or(git)
+            },
+            _ => git,
+        };
+
+        let tracked = read_bytes(self.fs, &repository.join("index"))
+            .and_then(|index| read_index(&index))
+            .unwrap_or_default();
+
+        let place = Place {
+            tracked: Some(Rc::new(tracked)),
+            relative: String::new(),
+            rules_from: self.rules.len(),
+            is_ignored: false,
+            is_repository: false,
+        };
+        self.read_ignore_file(&repository.join("info").join("exclude"), "");
+        self.read_ignore_file(&dir.join(".gitignore"), "");
+        place
+    }
+
+    fn read_ignore_file(&mut self, path: &Path, base: &str) {
+        if let Some(text) = read_text(self.fs, path) {
+            self.rules.extend(text.lines().filter_map(|line| Rule::parse(line, base)));
+        }
+    }
+
+    fn visit(&mut self, dir: &Path, relative: PathBuf, entry: &Entry, place: Place) -> Split {
+        let rules_count = self.rules.len();
+        let has_child = |name: &str| entry.children_iter().any(|child| child.name() == name);
+
+        let place = if place.is_repository {
+            place
+        } else if has_child(".git/") || has_child(".git") {
+            self.enter_work_tree(dir)
+        } else {
+            if place.tracked.is_some() && has_child(".gitignore") {
+                self.read_ignore_file(&dir.join(".gitignore"), &place.relative);
+            }
+            place
+        };
+
+        let mut split = Split::default();
+        for child in entry.children_iter() {
+            let name = child.name().trim_end_matches('/');
+            if child.is_file() || child.is_archive() {
+                split.add(place.status(name, &self.rules), child.size());
+            } else {
+                let child_place = place.directory(name, &self.rules);
+                let path = dir.join(name);
+                split.add_split(&self.visit(&path, relative.join(name), child, child_place));
+            }
+        }
+
+        // What was read in this directory only applies below it.
+        self.rules.truncate(rules_count);
+        self.splits.insert(relative, split);
+        split
+    }
+}
+
+// Where in a work tree a directory is. The relative path is the one from the top of the work
+// tree, ending with a slash unless it is empty. Only the ignore rules from rules_from on are for
+// this work tree.
+#[derive(Debug, Default, Clone)]
+struct Place {
+    tracked: Option<Rc<HashSet<String>>>,
+    relative: String,
+    rules_from: usize,
+    is_ignored: bool,
+    is_repository: bool,
+}
+
+impl Place {
+    fn directory(&self, name: &str, rules: &[Rule]) -> Place {
+        if self.tracked.is_none() {
+            return self.clone();
+        }
+
+        let path = format!("{}{}", self.relative, name);
+        Place {
+            tracked: self.tracked.clone(),
+            is_ignored: self.is_ignored || self.is_ignored_path(&path, true, rules),
+            is_repository: self.is_repository || path == ".git",
+            relative: path + "/",
+            rules_from: self.rules_from,
+        }
+    }
+
+    // Tracked files stay tracked even when they match an ignore rule, but nothing in an ignored
+    // directory can be added back by a later rule.
+    fn status(&self, name: &str, rules: &[Rule]) -> Status {
+        let tracked = match self.tracked {
+            Some(ref tracked) => tracked,
+            None => return Status::Outside,
+        };
+
+        let path = format!("{}{}", self.relative, name);
+        if self.is_repository || path == ".git" {
+            Status::Repository
+        } else if tracked.contains(&path) {
+            Status::Tracked
+        } else if self.is_ignored || self.is_ignored_path(&path, false, rules) {
+            Status::Ignored
+        } else {
+            Status::Untracked
+        }
+    }
+
+    // The last rule that matches decides, and rules from deeper ignore files come later.
+    fn is_ignored_path(&self, path: &str, is_dir: bool, rules: &[Rule]) -> bool {
+        rules[self.rules_from..].iter().rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .map(|rule| !rule.is_negated)
+            .unwrap_or(false)
+    }
+}
+
+// A line of an ignore file. Patterns with a slash in them are matched against the path from the
+// directory of the ignore file, and other patterns against the name at any depth.
+#[derive(Debug, PartialEq, Eq)]
+struct Rule {
+    base: String,
+    pattern: Vec<char>,
+    is_negated: bool,
+    is_anchored: bool,
+    only_directories: bool,
+}
+
+impl Rule {
+    fn parse(line: &str, base: &str) -> Option<Rule> {
+        let line = line.trim_end_matches('\r');
+        let line = if line.ends_with("\\ ") { line } else { line.trim_end_matches(' ') };
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (is_negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').filter(|rest| {
+                rest.starts_with('#') || rest.starts_with('!')
+            }).unwrap_or(line)),
+        };
+        let (only_directories, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Rule {
+            base: base.to_string(),
+            pattern: line.strip_prefix('/').unwrap_or(line).chars().collect(),
+            is_negated: is_negated,
+            is_anchored: line.contains('/'),
+            only_directories: only_directories,
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.only_directories && !is_dir {
+            return false;
+        }
+        let path = match path.strip_prefix(self.base.as_str()) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        let text: Vec<char> = if self.is_anchored {
+            path.chars().collect()
+        } else {
+            path.rsplit('/').next().unwrap_or(path).chars().collect()
+        };
+        glob_matches(&self.pattern, &text)
+    }
+}
+
+// Wildcards do not match slashes, except for "**" which matches any number of directories.
+fn glob_matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(&'*') if pattern.get(1) == Some(&'*') => {
+            match pattern.get(2) {
+                Some(&'/') => (0..text.len() + 1)
+                    .filter(|&index| index == 0 || text[index - 1] == '/')
+                    .any(|index| glob_matches(&pattern[3..], &text[index..])),
+                _ => (0..text.len() + 1).any(|index| glob_matches(&pattern[2..], &text[index..])),
+            }
+        },
+        Some(&'*') => {
+            (0..text.len() + 1)
+                .take_while(|&index| index == 0 || text[index - 1] != '/')
+                .any(|index| glob_matches(&pattern[1..], &text[index..]))
+        },
+        Some(&'?') => {
+            text.first().is_some_and(|&c| c != '/') && glob_matches(&pattern[1..], &text[1..])
+        },
+        Some(&'[') => {
+            match (class_matches(pattern, text.first()), text.is_empty()) {
+                (Some((true, length)), false) => glob_matches(&pattern[length..], &text[1..]),
+                (Some(_), _) => false,
+                (None, _) => literal_matches(pattern, text),
+            }
+        },
+        Some(&'\\') if pattern.len() > 1 => literal_matches(&pattern[1..], text),
+        Some(_) => literal_matches(pattern, text),
+    }
+}
+
+fn literal_matches(pattern: &[char], text: &[char]) -> bool {
+    text.first() == pattern.first() && glob_matches(&pattern[1..], &text[1..])
+}
+
+// Whether a class like "[a-z]" or "[!0-9]" at the start of the pattern matches the character, and
+// how long the class is. Patterns without a closing bracket have no class.
+fn class_matches(pattern: &[char], c: Option<&char>) -> Option<(bool, usize)> {
+    let is_negated = pattern.get(1) == Some(&'!') || pattern.get(1) == Some(&'^');
+    let start = if is_negated { 2 } else { 1 };
+    // A bracket right at the start is part of the class.
+    let end = start + 1 + pattern.iter().skip(start + 1).position(|&c| c == ']')?;
+    let class = &pattern[start..end];
+    let c = match c {
+        Some(&c) if c != '/' => c,
+        _ => return Some((false, end + 1)),
+    };
+
+    let mut index = 0;
+    let mut matches = false;
+    while index < class.len() {
+        if index + 2 < class.len() && class[index + 1] == '-' {
+            matches = matches || (class[index] <= c && c <= class[index + 2]);
+            index += 3;
+        } else {
+            matches = matches || class[index] == c;
+            index += 1;
+        }
+    }
+    Some((matches != is_negated, end + 1))
+}
+
+// Joins paths like "../.git/modules/sub" to the directory without looking at the file system.
+fn join(dir: &Path, path: &str) -> PathBuf {
+    let mut joined = dir.to_path_buf();
+    for component in Path::new(path).components() {
+        match component {
+            Component::ParentDir => {
+                joined.pop();
+            },
+            Component::CurDir => {},
+            component => joined.push(component.as_os_str()),
+        }
+    }
+    joined
+}
+
+fn read_bytes<F: FileSystem>(fs: &F, path: &Path) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    fs.open(path).and_then(|mut file| file.read_to_end(&mut bytes)).ok()?;
+    Some(bytes)
+}
+
+fn read_text<F: FileSystem>(fs: &F, path: &Path) -> Option<String> {
+    read_bytes(fs, path).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// The paths in a git index of version 2, 3 or 4. Entries start with 62 bytes of stat data, the
+// object ID and flags, and version 4 leaves out what the path has in common with the one before.
+fn read_index(index: &[u8]) -> Option<HashSet<String>> {
+    if index.len() < 12 || &index[0..4] != INDEX_SIGNATURE {
+        return None;
+    }
+    let version = be32(&index[4..]);
+    let count = be32(&index[8..]);
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+
+    let mut paths = HashSet::new();
+    let mut previous: Vec<u8> = Vec::new();
+    let mut offset = 12;
+    for _ in 0..count {
+        let start = offset;
+        let flags = be16(index.get(offset + 60..offset + 62)?);
+        offset += 62;
+        if version >= 3 && flags & 0x4000 != 0 {
+            offset += 2;
+        }
+
+        let mut path = if version == 4 {
+            let (removed, length) = varint(index.get(offset..)?)?;
+            offset += length;
+            let kept = previous.len().checked_sub(removed as usize)?;
+            previous[..kept].to_vec()
+        } else {
+            Vec::new()
+        };
+        let end = offset + index.get(offset..)?.iter().position(|&byte| byte == 0)?;
+        path.extend_from_slice(&index[offset..end]);
+        offset = end + 1;
+        if version < 4 {
+            // Entries are padded with NULs to a multiple of eight bytes.
+            offset = start + (end - start + 8) / 8 * 8;
+        }
+
+        paths.insert(String::from_utf8_lossy(&path).into_owned());
+        previous = path;
+    }
+    Some(paths)
+}
+
+// The offset encoding of git, where every continuation adds one so there is only one way to write
+// every number.
+fn varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = (*bytes.first()? & 0x7f) as u64;
+    let mut length = 1;
+    while bytes[length - 1] & 0x80 != 0 {
+        let byte = *bytes.get(length)?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as u64;
+        length += 1;
+    }
+    Some((value, length))
+}
+
+fn be16(bytes: &[u8]) -> u16 {
+    (bytes[0] as u16) << 8 | bytes[1] as u16
+}
+
+fn be32(bytes: &[u8]) -> u32 {
+    (be16(bytes) as u32) << 16 | be16(&bytes[2..]) as u32
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use filesystem::MemoryFileSystem;
+
+    // Makes an index of version 2 with the paths in it, leaving out everything else.
+    pub fn index(paths: &[&str]) -> Vec<u8> {
+        let mut index = b"DIRC\0\0\0\x02".to_vec();
+        index.extend_from_slice(&[0, 0, 0, paths.len() as u8]);
+        for path in paths {
+            let mut entry = vec![0; 62];
+            entry[61] = path.len() as u8;
+            entry.extend_from_slice(path.as_bytes());
+            let padding = 8 - entry.len() % 8;
+            entry.extend(vec![0; padding]);
+            index.extend(entry);
+        }
+        index
+    }
+
+    fn splits(fs: &MemoryFileSystem, path: &str) -> HashMap<PathBuf, Vec<(String, u64)>> {
+        let entry = Entry::for_path_in(fs, Path::new(path)).unwrap();
+        classify(fs, Path::new(path), &entry).into_iter()
+            .map(|(path, groups)| {
+                (path, groups.into_iter().map(|group| (group.name, group.size)).collect())
+            })
+            .collect()
+    }
+
+    fn split(groups: &[(&str, u64)]) -> Vec<(String, u64)> {
+        groups.iter().map(|&(name, size)| (name.to_string(), size)).collect()
+    }
+
+    fn repository() -> MemoryFileSystem {
+        MemoryFileSystem::new()
+            .contents("repo/.git/index", index(&["README", "src/main.rs", "target/keep"]))
+            .contents("repo/.gitignore", b"target/\n*.log\n!important.log\n".to_vec())
+            .file("repo/README", 100)
+            .file("repo/notes.txt", 10)
+            .file("repo/debug.log", 20)
+            .file("repo/important.log", 30)
+            .file("repo/src/main.rs", 200)
+            .file("repo/src/new.rs", 40)
+            .contents("repo/src/.gitignore", b"/generated\n".to_vec())
+            .file("repo/src/generated/code.rs", 500)
+            .file("repo/target/debug/app", 1000)
+            .file("repo/target/keep", 5)
+    }
+
+    #[test]
+    fn it_splits_work_trees_by_status() {
+        let fs = repository();
+        let splits = splits(&fs, "repo");
+        let index_size = index(&["README", "src/main.rs", "target/keep"]).len() as u64;
+
+        assert_eq!(splits[Path::new("")], split(&[
+            ("tracked", 305),
+            ("untracked", 10 + 30 + 40 + 29 + 11),
+            ("ignored", 20 + 500 + 1000),
+            (".git", index_size),
+        ]));
+        assert_eq!(splits[Path::new("src")], split(&[
+            ("tracked", 200),
+            ("untracked", 40 + 11),
+            ("ignored", 500),
+        ]));
+        assert_eq!(splits[Path::new("target/debug")], split(&[("ignored", 1000)]));
+        assert_eq!(splits[Path::new("target")], split(&[("tracked", 5), ("ignored", 1000)]));
+    }
+
+    #[test]
+    fn it_finds_the_work_tree_above_the_path() {
+        let fs = repository().file("outside/file", 7);
+
+        assert_eq!(splits(&fs, "repo/src")[Path::new("")], split(&[
+            ("tracked", 200),
+            ("untracked", 40 + 11),
+            ("ignored", 500),
+        ]));
+        assert_eq!(splits(&fs, "repo/target/debug")[Path::new("")], split(&[("ignored", 1000)]));
+        assert_eq!(splits(&fs, "repo/README")[Path::new("")], split(&[("tracked", 100)]));
+        assert_eq!(splits(&fs, "outside")[Path::new("")], split(&[("not in git", 7)]));
+    }
+
+    #[test]
+    fn it_finds_work_trees_below_the_path() {
+        let fs = MemoryFileSystem::new()
+            .file("projects/loose", 1)
+            .contents("projects/a/.git/index", index(&["file"]))
+            .file("projects/a/file", 10)
+            .contents("projects/a/sub/.git", b"gitdir: ../.git/modules/sub\n".to_vec())
+            .contents("projects/a/.git/modules/sub/index", index(&["inner"]))
+            .file("projects/a/sub/inner", 100)
+            .file("projects/a/sub/other", 1000);
+        let splits = splits(&fs, "projects");
+
+        assert_eq!(splits[Path::new("a/sub")], split(&[
+            ("tracked", 100),
+            ("untracked", 1000),
+            (".git", 28),
+        ]));
+        assert_eq!(splits[Path::new("")][0], ("tracked".to_string(), 110));
+        assert_eq!(splits[Path::new("")].last(), Some(&("not in git".to_string(), 1)));
+    }
+
+    #[test]
+    fn it_matches_ignore_patterns_like_git() {
+        let matches = |pattern: &str, path: &str, is_dir: bool| {
+            Rule::parse(pattern, "").unwrap().matches(path, is_dir)
+        };
+
+        assert!(matches("*.o", "src/main.o", false));
+        assert!(!matches("*.o", "src/main.c", false));
+        assert!(matches("build/", "a/build", true));
+        assert!(!matches("build/", "a/build", false));
+        assert!(matches("/build", "build", true));
+        assert!(!matches("/build", "a/build", true));
+        assert!(matches("doc/*.txt", "doc/notes.txt", false));
+        assert!(!matches("doc/*.txt", "doc/more/notes.txt", false));
+        assert!(matches("**/logs", "a/b/logs", true));
+        assert!(matches("**/logs", "logs", true));
+        assert!(matches("a/**/b", "a/x/y/b", false));
+        assert!(matches("a/**/b", "a/b", false));
+        assert!(matches("out/**", "out/a/b", false));
+        assert!(matches("file.[ch]", "file.h", false));
+        assert!(matches("file.[!ch]", "file.o", false));
+        assert!(matches("v[0-9]", "v7", false));
+        assert!(matches("\\#notes", "#notes", false));
+        assert!(matches("?.md", "a.md", false));
+
+        assert_eq!(Rule::parse("# comment", ""), None);
+        assert_eq!(Rule::parse("   ", ""), None);
+        assert!(Rule::parse("!keep.log", "").unwrap().is_negated);
+        assert!(!Rule::parse("*.log", "sub/").unwrap().matches("other/a.log", false));
+        assert!(Rule::parse("*.log", "sub/").unwrap().matches("sub/deep/a.log", false));
+    }
+
+    #[test]
+    fn it_reads_every_version_of_the_index() {
+        let paths: HashSet<String> = vec!["a", "dir/b"].into_iter().map(String::from).collect();
+        assert_eq!(read_index(&index(&["a", "dir/b"])), Some(paths.clone()));
+
+        // Version 4 only has what is new in every path.
+        let mut index = b"DIRC\0\0\0\x04\0\0\0\x02".to_vec();
+        index.extend(vec![0; 62]);
+        index.extend_from_slice(b"\0dir/a\0");
+        index.extend(vec![0; 62]);
+        index.extend_from_slice(b"\x01b\0");
+        let paths: HashSet<String> = vec!["dir/a", "dir/b"].into_iter().map(String::from).collect();
+        assert_eq!(read_index(&index), Some(paths));
+
+        assert_eq!(read_index(b"not an index"), None);
+        assert_eq!(varint(&[0x80, 0x01]), Some((129, 2)));
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::{self, Write};
+
+use options::Options;
+use modes::DisplayableEntry;
+use root::Root;
+use utils;
+use utils::{SizeFormat, Units};
+
+const TEMPLATE: &'static str = include_str!("report.html");
+
+pub fn write_html<W: Write>(root: &Root, options: &Options, out: &mut W) -> io::Result<()> {
+    writeln!(out, "{}", render(root, options))
+}
+
+// The report is a single document without any external resources. The scanned tree is embedded
+// as a JSON literal and the treemap and table are drawn by the inline script.
+fn render(root: &Root, options: &Options) -> String {
+    let mut data = String::new();
+    write_json(root, options, 0, &mut data);
+
+    fill(TEMPLATE, &[
+        ("{{TITLE}}", &utils::escape_markup(root.name())),
+        ("{{DATA}}", &data),
+        ("{{FORMAT}}", &format_json(options.size_format())),
+    ])
+}
+
+// Replaces the placeholders in a single pass, so names that look like placeholders are left alone
+// in what was put in.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|&&(placeholder, _)| rest.starts_with(placeholder)) {
+            Some(&(placeholder, value)) => {
+                filled.push_str(value);
+                rest = &rest[placeholder.len()..];
+            },
+            None => {
+                filled.push_str("{{");
+                rest = &rest[2..];
+            },
+        }
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+fn format_json(format: &SizeFormat) -> String {
+    let (units, block_size) = match format.units {
+        Units::Decimal => ("decimal", 1),
+        Units::Binary => ("binary", 1),
+        Units::Bytes => ("bytes", 1),
+        Units::Blocks(block_size) => ("blocks", block_size),
+    };
+
+    let grouping = match format.grouping {
+        Some(ref grouping) => format!("{{\"separator\":{},\"decimal\":{}}}",
+                                      json_string(&grouping.separator),
+                                      json_string(&grouping.decimal_mark.to_string())),
+        None => "null".to_string(),
+    };
+
+    format!("{{\"units\":\"{}\",\"block\":{},\"precision\":{},\"grouping\":{}}}",
+            units, block_size, format.precision, grouping)
+}
+
+fn write_json<T: DisplayableEntry>(entry: &T, options: &Options, level: usize, out: &mut String) {
+    out.push_str("{\"n\":");
+    out.push_str(&json_string(entry.name()));
+    out.push_str(",\"s\":");
+    out.push_str(&entry.size().to_string());
+
+    if !entry.is_file() {
+        out.push_str(",\"c\":[");
+        if options.depth_accepts(level) {
+            let mut first = true;
+            for child in entry.children_iter() {
+                if !options.should_show_hidden() && child.is_hidden() {
+                    continue;
+                }
+                if !first {
+                    out.push(',');
+                }
+                write_json(child, options, level + 1, out);
+                first = false;
+            }
+        }
+        out.push(']');
+    }
+
+    out.push('}');
+}
+
+fn json_string(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+    escaped.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            // Escaping < makes sure a file name can never close the surrounding <script> tag.
+            '<' => escaped.push_str("\\u003c"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use formats::Format;
+    use options::Depth;
+    use filesystem::MemoryFileSystem;
+    use root::Root;
+    use std::path::Path;
+    use utils::DigitGrouping;
+
+    #[test]
+    fn it_escapes_json_strings() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("</script>"), "\"\\u003c/script>\"");
+        assert_eq!(json_string("bell\u{7}"), "\"bell\\u0007\"");
+    }
+
+    #[test]
+    fn it_embeds_the_size_format() {
+        let default = SizeFormat::default();
+        let blocks = SizeFormat {
+            units: Units::Blocks(4096),
+            precision: 0,
+            grouping: Some(DigitGrouping::with_separator(".")),
+        };
+
+        assert_eq!(format_json(&default),
+                   "{\"units\":\"decimal\",\"block\":1,\"precision\":2,\"grouping\":null}");
+        assert_eq!(format_json(&blocks),
+                   "{\"units\":\"blocks\",\"block\":4096,\"precision\":0,\
+                    \"grouping\":{\"separator\":\".\",\"decimal\":\",\"}}");
+    }
+
+    #[test]
+    fn it_embeds_file_roots_without_children() {
+        let options = Options::builder().build();
+        let root = Root::for_path(Path::new("./LICENSE")).unwrap();
+        let mut json = String::new();
+        write_json(&root, &options, 0, &mut json);
+
+        assert_eq!(json, format!("{{\"n\":\"./LICENSE\",\"s\":{}}}", root.size()));
+    }
+
+    #[test]
+    fn it_embeds_children_of_directories() {
+        let options = Options::builder().depth(Depth::Limited(1)).build();
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let mut json = String::new();
+        write_json(&root, &options, 0, &mut json);
+
+        assert!(json.starts_with(&format!("{{\"n\":\"./src/\",\"s\":{},\"c\":[", root.size())));
+        assert!(json.contains("{\"n\":\"main.rs\",\"s\":"));
+    }
+
+    #[test]
+    fn it_renders_a_self_contained_document() {
+        let options = Options::builder().build();
+        let root = Root::for_path(Path::new("./LICENSE")).unwrap();
+        let html = render(&root, &options);
+
+        assert!(html.contains("<title>dutop: ./LICENSE</title>"));
+        assert!(html.contains("var ROOT = {\"n\":\"./LICENSE\""));
+        assert!(!html.contains("{{DATA}}"));
+        assert!(!html.contains("<script src"));
+        assert!(!html.contains("<link"));
+    }
+
+    #[test]
+    fn it_leaves_placeholders_in_names_alone() {
+        let fs = MemoryFileSystem::new().file("{{DATA}}/{{FORMAT}}", 10);
+        let options = Options::builder().format(Format::Html).build();
+        let root = Root::for_path_in(&fs, Path::new("{{DATA}}")).unwrap();
+        let html = render(&root, &options);
+
+        assert!(html.contains("<title>dutop: {{DATA}}/</title>"));
+        assert!(html.contains("var ROOT = {\"n\":\"{{DATA}}/\",\"s\":10,\"c\":[\
+                               {\"n\":\"{{FORMAT}}\",\"s\":10}]};"));
+        assert!(html.contains("var FORMAT = {\"units\""));
+    }
+
+    #[test]
+    fn it_embeds_everything_unless_given_a_depth() {
+        let fs = MemoryFileSystem::new().file("root/a/b/c", 10);
+        let root = Root::for_path_in(&fs, Path::new("root")).unwrap();
+
+        let html = render(&root, &Options::builder().format(Format::Html).build());
+        assert!(html.contains("{\"n\":\"c\",\"s\":10}"));
+
+        let options = Options::builder().format(Format::Html).depth(Depth::Limited(1)).build();
+        let html = render(&root, &options);
+        assert!(html.contains("{\"n\":\"a/\",\"s\":10,\"c\":[]}"));
+    }
+}
@@ -0,0 +1,216 @@
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, w: f64, h: f64) -> Rect {
+        Rect { x: x, y: y, w: w, h: h }
+    }
+
+    pub fn area(&self) -> f64 {
+        self.w * self.h
+    }
+
+    // Shrinks the rectangle by the given amounts; used to leave room for borders and labels.
+    pub fn inset(&self, top: f64, right: f64, bottom: f64, left: f64) -> Rect {
+        Rect::new(
+            self.x + left,
+            self.y + top,
+            (self.w - left - right).max(0.0),
+            (self.h - top - bottom).max(0.0),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arc {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Arc {
+    pub fn full() -> Arc {
+        Arc { start: 0.0, end: 2.0 * PI }
+    }
+
+    pub fn sweep(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+// Squarified treemap (Bruls, Huizing, van Wijk). Returns one rectangle per size, in the same order
+// as the sizes were given. Every rectangle gets an area proportional to its size, and together
+// they fill the bounds.
+pub fn squarify(sizes: &[u64], bounds: Rect) -> Vec<Rect> {
+    let mut rects = vec![Rect::new(bounds.x, bounds.y, 0.0, 0.0); sizes.len()];
+    let total = sizes.iter().fold(0, |sum, size| sum + size);
+    if total == 0 || bounds.area() <= 0.0 {
+        return rects;
+    }
+
+    let scale = bounds.area() / total as f64;
+    let mut order: Vec<usize> = (0..sizes.len()).filter(|&index| sizes[index] > 0).collect();
+    // Note: We change the ordering to get in descending order
+    order.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]));
+    let areas: Vec<f64> = order.iter().map(|&index| sizes[index] as f64 * scale).collect();
+
+    let mut free = bounds;
+    let mut row_start = 0;
+    let mut row_end = 0;
+
+    while row_end < order.len() {
+        let side = free.w.min(free.h);
+        let current = &areas[row_start..row_end];
+        let candidate = &areas[row_start..row_end + 1];
+
+        if current.is_empty() || worst_ratio(candidate, side) <= worst_ratio(current, side) {
+            row_end += 1;
+        } else {
+            free = place_row(&order[row_start..row_end], current, free, &mut rects);
+            row_start = row_end;
+        }
+    }
+    place_row(&order[row_start..row_end], &areas[row_start..row_end], free, &mut rects);
+
+    rects
+}
+
+fn worst_ratio(row: &[f64], side: f64) -> f64 {
+    let sum = row.iter().fold(0.0, |sum, area| sum + area);
+    let max = row.iter().cloned().fold(0.0, f64::max);
+    let min = row.iter().cloned().fold(::std::f64::INFINITY, f64::min);
+
+    ((side * side * max) / (sum * sum)).max((sum * sum) / (side * side * min))
+}
+
+// Lays out a row along the shorter side of the free space and returns what space is left.
+fn place_row(indices: &[usize], areas: &[f64], free: Rect, rects: &mut Vec<Rect>) -> Rect {
+    let sum = areas.iter().fold(0.0, |sum, area| sum + area);
+    if sum <= 0.0 {
+        return free;
+    }
+
+    let mut offset = 0.0;
+    if free.w >= free.h {
+        let width = sum / free.h;
+        for (&index, area) in indices.iter().zip(areas) {
+            let height = area / width;
+            rects[index] = Rect::new(free.x, free.y + offset, width, height);
+            offset += height;
+        }
+        Rect::new(free.x + width, free.y, free.w - width, free.h)
+    } else {
+        let height = sum / free.w;
+        for (&index, area) in indices.iter().zip(areas) {
+            let width = area / height;
+            rects[index] = Rect::new(free.x + offset, free.y, width, height);
+            offset += width;
+        }
+        Rect::new(free.x, free.y + height, free.w, free.h - height)
+    }
+}
+
+// Splits an arc between the sizes, giving each of them a sweep proportional to its size. Used by
+// the sunburst layout, where every ring is the children of the ring inside of it.
+pub fn split_arc(sizes: &[u64], arc: Arc) -> Vec<Arc> {
+    let total = sizes.iter().fold(0, |sum, size| sum + size);
+    let mut start = arc.start;
+
+    sizes.iter().map(|&size| {
+        let sweep = if total == 0 { 0.0 } else { arc.sweep() * size as f64 / total as f64 };
+        let child = Arc { start: start, end: start + sweep };
+        start += sweep;
+        child
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modes::DisplayableEntry;
+    use root::Root;
+    use std::f64::consts::PI;
+    use std::path::Path;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} is not close to {}", a, b);
+    }
+
+    fn contains(outer: &Rect, inner: &Rect) -> bool {
+        inner.x >= outer.x - 1e-6 && inner.y >= outer.y - 1e-6 &&
+            inner.x + inner.w <= outer.x + outer.w + 1e-6 &&
+            inner.y + inner.h <= outer.y + outer.h + 1e-6
+    }
+
+    #[test]
+    fn it_gives_rectangles_areas_proportional_to_sizes() {
+        let sizes = vec![6, 6, 4, 3, 2, 2, 1];
+        let bounds = Rect::new(0.0, 0.0, 6.0, 4.0);
+        let rects = squarify(&sizes, bounds);
+
+        assert_eq!(rects.len(), sizes.len());
+        for (rect, &size) in rects.iter().zip(&sizes) {
+            assert_close(rect.area(), size as f64);
+            assert!(contains(&bounds, rect));
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_order_of_unsorted_sizes() {
+        let sizes = vec![1, 10, 0, 5];
+        let rects = squarify(&sizes, Rect::new(10.0, 20.0, 40.0, 10.0));
+
+        assert_close(rects[0].area(), 25.0);
+        assert_close(rects[1].area(), 250.0);
+        assert_close(rects[2].area(), 0.0);
+        assert_close(rects[3].area(), 125.0);
+    }
+
+    #[test]
+    fn it_lays_out_nothing_when_everything_is_empty() {
+        let rects = squarify(&[0, 0], Rect::new(0.0, 0.0, 10.0, 10.0));
+        assert!(rects.iter().all(|rect| rect.area() == 0.0));
+        assert!(squarify(&[], Rect::new(0.0, 0.0, 10.0, 10.0)).is_empty());
+    }
+
+    #[test]
+    fn it_lays_out_entries_proportional_to_their_size() {
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let sizes: Vec<u64> = root.children_iter().map(|child| child.size()).collect();
+        let total = sizes.iter().fold(0, |sum, size| sum + size) as f64;
+        let bounds = Rect::new(0.0, 0.0, 800.0, 600.0);
+
+        let rects = squarify(&sizes, bounds);
+        let covered = rects.iter().fold(0.0, |sum, rect| sum + rect.area());
+
+        assert_close(covered, bounds.area());
+        for (rect, child) in rects.iter().zip(root.children_iter()) {
+            assert_close(rect.area() / bounds.area(), child.size() as f64 / total);
+        }
+    }
+
+    #[test]
+    fn it_splits_arcs_proportional_to_sizes() {
+        let arcs = split_arc(&[3, 1], Arc::full());
+
+        assert_close(arcs[0].start, 0.0);
+        assert_close(arcs[0].sweep(), 1.5 * PI);
+        assert_close(arcs[1].start, 1.5 * PI);
+        assert_close(arcs[1].end, 2.0 * PI);
+    }
+
+    #[test]
+    fn it_splits_partial_arcs() {
+        let arcs = split_arc(&[1, 1, 2], Arc { start: PI, end: 2.0 * PI });
+
+        assert_close(arcs[0].sweep(), 0.25 * PI);
+        assert_close(arcs[1].start, 1.25 * PI);
+        assert_close(arcs[2].sweep(), 0.5 * PI);
+    }
+}
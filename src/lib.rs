@@ -0,0 +1,94 @@
+//! Finds out what takes up room on disk.
+//!
+//! A directory is scanned into a tree of entries, where every directory knows the total size of
+//! what is in it and its children are sorted from large to small:
+//!
+//! ```
+//! use std::path::Path;
+//! use dutop::{DisplayableEntry, Scanner};
+//!
+//! let root = Scanner::new().scan(Path::new("src")).unwrap();
+//! for child in root.children_iter() {
+//!     println!("{} takes up {} bytes", child.name(), child.size());
+//! }
+//!
+//! let files = root.walk().filter(|&(_, entry)| entry.is_file()).count();
+//! println!("{} files in total", files);
+//! ```
+//!
+//! The tree can be restricted with a `Filter`, compared with an earlier scan with `diff::compare`
+//! and saved and loaded with the `snapshot` module. It is shown the same way as by the `dutop`
+//! command by giving a `Format` and `Options` to render to anything that can be written to. The
+//! options start out like the command without arguments:
+//!
+//! ```
+//! use std::path::Path;
+//! use dutop::{Limit, Mode, Options, RealFileSystem, Scanner};
+//!
+//! let options = Options::builder().mode(Mode::Files).limit(Limit::Limited(3)).build();
+//! let root = Scanner::new().scan(Path::new("src")).unwrap();
+//!
+//! let mut out = Vec::new();
+//! options.format().render(&RealFileSystem, root, &options, &mut out).unwrap();
+//! // The root, followed by its three largest files.
+//! assert_eq!(String::from_utf8(out).unwrap().lines().count(), 4);
+//! ```
+//!
+//! Scanners look at the file system of the machine unless given another `FileSystem`, like a
+//! `MemoryFileSystem` that is made up in a test:
+//!
+//! ```
+//! use std::path::Path;
+//! use dutop::{DisplayableEntry, MemoryFileSystem, Scanner};
+//!
+//! let fs = MemoryFileSystem::new().file("project/src/main.rs", 1200).file("project/README", 300);
+//! let root = Scanner::with_file_system(fs).scan(Path::new("project")).unwrap();
+//! assert_eq!(root.size(), 1500);
+//! ```
+//!
+//! Everything at the top level of the crate is meant to stay; what is only in the modules may
+//! still change.
+
+extern crate flate2;
+extern crate libc;
+
+pub mod accounts;
+mod archives;
+pub mod breakdown;
+pub mod cache;
+pub mod cleanable;
+pub mod colors;
+pub mod diff;
+mod duplicates;
+mod empty;
+mod git;
+pub mod utils;
+pub mod entry;
+pub mod filesystem;
+pub mod filters;
+pub mod root;
+pub mod scan;
+pub mod snapshot;
+pub mod modes;
+pub mod options;
+pub mod formats;
+mod folded;
+mod html;
+mod layout;
+pub mod svg;
+pub mod terminal;
+mod tui;
+pub mod walk;
+pub mod watch;
+
+pub use options::{Depth, Limit, Options, OptionsBuilder};
+pub use cache::Cache;
+pub use entry::{Entry, Timestamp};
+pub use filesystem::{FileSystem, MemoryFileSystem, RealFileSystem};
+pub use filters::Filter;
+pub use formats::Format;
+pub use modes::{DisplayableEntry, Mode};
+pub use root::Root;
+pub use scan::Scanner;
+pub use snapshot::Snapshot;
+pub use walk::Walk;
@@ -1,21 +1,92 @@
 #[macro_use]
 extern crate clap;
+extern crate dutop;
 
 mod arguments;
-mod utils;
-mod entry;
-mod root;
-mod modes;
 
-use root::Root;
+use std::io;
+use std::path::Path;
+use std::process::exit;
+
+use dutop::{diff, snapshot, watch};
+use dutop::{Cache, Filter, Options, Root, Scanner};
 
 fn main() {
     let options = arguments::parse();
-    for root_path in options.roots() {
-        match Root::for_path(&root_path) {
-            Ok(root) => options.mode().work(root, &options),
-            Err(message) =>
-                println!("{}: {}", root_path.to_string_lossy(), message)
+    let filter = Filter::for_options(&options);
+
+    // Keeping a scan around for the next one is only worth it when there will be a next one.
+    let scanner = Scanner::new().into_archives(options.should_look_into_archives());
+    let mut scanner = match options.cache_file() {
+        Some(path) => scanner.cache(Cache::load(path)),
+        None if options.watch_interval().is_some() => scanner.cache(Cache::new()),
+        None => scanner,
+    };
+
+    if let Some(interval) = options.watch_interval() {
+        watch::run(interval, &options, || {
+            let roots = scan(&options, &mut scanner);
+            roots.into_iter().map(|root| filter.apply(root)).collect()
+        });
+    }
+
+    let roots = match options.snapshot_to_load() {
+        Some(path) => load(path),
+        None => scan(&options, &mut scanner),
+    };
+
+    if let Some(path) = options.snapshot_to_save() {
+        if let Err(message) = snapshot::save(path, &roots) {
+            println!("{}: {}", path.to_string_lossy(), message);
+            exit(1);
+        }
+    }
+
+    match options.snapshot_to_diff() {
+        Some(path) => {
+            let old_roots = load(path).into_iter().map(|root| filter.apply(root));
+            let new_roots = roots.into_iter().map(|root| filter.apply(root));
+            let stdout = io::stdout();
+            let _ = diff::write_changes(path, old_roots.collect(), new_roots.collect(), &options,
+                                        &mut stdout.lock());
+        },
+        None => {
+            for root in roots {
+                options.format().work(scanner.file_system(), filter.apply(root), &options);
+            }
+        },
+    }
+}
+
+fn scan(options: &Options, scanner: &mut Scanner) -> Vec<Root> {
+    let roots = options.roots().into_iter().filter_map(|root_path| {
+        match scanner.scan(&root_path) {
+            Ok(root) => Some(root),
+            Err(message) => {
+                println!("{}: {}", root_path.to_string_lossy(), message);
+                None
+            }
+        }
+    }).collect();
+
+    if let Some(path) = options.cache_file() {
+        if let Err(message) = scanner.save_cache(path) {
+            println!("{}: {}", path.to_string_lossy(), message);
+        }
+    }
+    roots
+}
+
+fn load(path: &Path) -> Vec<Root> {
+    match snapshot::load(path) {
+        Ok(snapshot) => {
+            // Goes to stderr so it does not end up in output that is meant for other programs.
+            eprintln!("Using snapshot {} from {} ago", path.to_string_lossy(), snapshot.age());
+            snapshot.roots
+        },
+        Err(message) => {
+            println!("{}: {}", path.to_string_lossy(), message);
+            exit(1);
         }
     }
 }
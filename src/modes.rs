@@ -1,99 +1,1533 @@
+use std::env;
 use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
 use std::slice::Iter;
+use std::str::FromStr;
 
-use arguments::Options;
+use accounts::Accounts;
+use options::Options;
+use breakdown::{self, Group};
+use cleanable::{self, Cleanable};
+use colors::Palette;
+use duplicates;
+use empty::{self, EmptyGroup};
 use root::Root;
 use entry::Entry;
+use filesystem::FileSystem;
+use git;
+use tui;
+use utils::{self, SizeDisplay};
 
 pub trait DisplayableEntry : fmt::Display + Sized {
     type Child: DisplayableEntry;
 
     fn size(&self) -> u64;
     fn name(&self) -> &String;
-    fn children_iter(&self) -> Iter<Self::Child>;
+    fn children_iter(&self) -> Iter<'_, Self::Child>;
     fn is_file(&self) -> bool;
 
     fn is_hidden(&self) -> bool {
         self.name().chars().nth(0) == Some('.')
     }
+
+    // How much of an archive what is in it takes up.
+    fn compressed_size(&self) -> Option<u64> {
+        None
+    }
+
+    // How much what is in an archive takes up when unpacked.
+    fn uncompressed_size(&self) -> Option<u64> {
+        None
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Mode {
     Tree,
     Files,
+    Dirs,
+    Extensions,
+    Owners,
+    Groups,
+    Ages,
+    Git,
+    Duplicates,
+    Empty,
+    Cleanable,
+    Interactive,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TreeStyle {
+    Indent,
+    Unicode,
+    Ascii,
+}
+
+impl TreeStyle {
+    // Drawn in front of an entry.
+    fn branch(&self, is_last: bool) -> &'static str {
+        match (self, is_last) {
+            (&TreeStyle::Indent, _) => "  ",
+            (&TreeStyle::Unicode, false) => "├── ",
+            (&TreeStyle::Unicode, true) => "└── ",
+            (&TreeStyle::Ascii, false) => "|-- ",
+            (&TreeStyle::Ascii, true) => "`-- ",
+        }
+    }
+
+    // Drawn in front of the descendants of an entry.
+    fn continuation(&self, is_last: bool) -> &'static str {
+        match (self, is_last) {
+            (&TreeStyle::Indent, _) => "  ",
+            (&TreeStyle::Unicode, false) => "│   ",
+            (&TreeStyle::Ascii, false) => "|   ",
+            (_, true) => "    ",
+        }
+    }
+}
+
+impl FromStr for TreeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "indent" => Ok(TreeStyle::Indent),
+            "unicode" => Ok(TreeStyle::Unicode),
+            "ascii" => Ok(TreeStyle::Ascii),
+            _ => Err(format!("Unknown tree style \"{}\"", s)),
+        }
+    }
+}
+
+const PERCENT_WIDTH: usize = 7;
+const DEFAULT_BAR_WIDTH: usize = 20;
+const MIN_BAR_WIDTH: usize = 10;
+const MAX_BAR_WIDTH: usize = 40;
+const MIN_NAME_WIDTH: usize = 12;
+
+// A single line of output. The sizes of the parent and the root are kept so the percentage and bar
+// columns can be calculated when the rows are formatted. Rows that stand for a group of files have
+// the number of files in them, and what is in archives has its other size.
+struct Row {
+    prefix: String,
+    name: String,
+    is_file: bool,
+    size: u64,
+    parent_size: u64,
+    root_size: u64,
+    note: Option<String>,
 }
 
 impl Mode {
-    pub fn work(&self, root: Root, options: &Options) {
+    // Prints to stdout, or browses the tree in the terminal in interactive mode. The file system is
+    // the one the root was scanned from, for the modes that read files.
+    pub fn work<F: FileSystem>(&self, fs: &F, root: Root, options: &Options) {
+        match self {
+            &Mode::Interactive => tui::browse(fs, root, options),
+            _ => {
+                // Nothing is left to do when stdout is closed, like when piped into head.
+                let stdout = io::stdout();
+                let _ = self.render(fs, root, options, &mut stdout.lock());
+            },
+        }
+    }
+
+    // Like work, but writes to out. Interactive mode needs a terminal, so it is refused.
+    pub fn render<F: FileSystem, W: Write>(&self,
+                                           fs: &F,
+                                           root: Root,
+                                           options: &Options,
+                                           out: &mut W) -> io::Result<()> {
         match self {
-            &Mode::Tree => print_tree(root, options),
-            &Mode::Files => print_largest_files(root, options),
+            &Mode::Tree => write_tree(root, options, out),
+            &Mode::Files => write_largest_files(fs, root, options, out),
+            &Mode::Dirs => write_largest_directories(fs, root, options, out),
+            &Mode::Extensions => write_breakdown(root, options, breakdown::extension_key, out),
+            &Mode::Owners => {
+                let users = Accounts::users();
+                write_breakdown(root, options, |entry: &Entry| users.name(entry.uid()), out)
+            },
+            &Mode::Groups => {
+                let groups = Accounts::groups();
+                write_breakdown(root, options, |entry: &Entry| groups.name(entry.gid()), out)
+            },
+            &Mode::Ages => write_ages(root, options, out),
+            &Mode::Git => write_git(fs, root, options, out),
+            &Mode::Duplicates => write_duplicates(fs, root, options, out),
+            &Mode::Empty => write_empty(fs, root, options, out),
+            &Mode::Cleanable => write_cleanable(fs, root, options, out),
+            &Mode::Interactive => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                     "Interactive mode needs a terminal")),
         }
     }
 }
 
-fn print_tree<T: DisplayableEntry>(entry: T, options: &Options) {
-    print_indented_tree(&entry, options, 0);
+fn write_tree<T: DisplayableEntry, W: Write>(entry: T, options: &Options, out: &mut W)
+                                             -> io::Result<()> {
+    let mut rows = Vec::new();
+    let size = entry.size();
+    add_tree_rows(&entry, options, 0, String::new(), String::new(), size, size, &mut rows);
+
+    write_rows(&rows, options.tree_style(), options, out)
 }
 
-fn print_largest_files(root: Root, options: &Options) {
+fn write_largest_files<F: FileSystem, W: Write>(fs: &F, root: Root, options: &Options, out: &mut W)
+                                               -> io::Result<()> {
     if root.is_file() {
         // That was easy!
-        println!("{}", root);
+        write_rows(&[root_row(&root)], &TreeStyle::Indent, options, out)
     } else {
-        print_largest_files_in_directory(root, &options)
+        let rows = largest_file_rows(fs, &root, options);
+        write_rows(&rows, &TreeStyle::Indent, options, out)
     }
 }
 
-fn print_indented_tree<T: DisplayableEntry>(entry: &T, options: &Options, level: usize) {
-    println!("{0:1$}{2}", "", level * 2, entry);
+fn write_largest_directories<F: FileSystem, W: Write>(fs: &F,
+                                                      root: Root,
+                                                      options: &Options,
+                                                      out: &mut W) -> io::Result<()> {
+    let rows = largest_directory_rows(fs, &root, options);
+    write_rows(&rows, &TreeStyle::Indent, options, out)
+}
+
+fn write_rows<W: Write>(rows: &[Row], style: &TreeStyle, options: &Options, out: &mut W)
+                        -> io::Result<()> {
+    for line in format_rows(rows, style, options, options.palette(), options.width()) {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn root_row<T: DisplayableEntry>(root: &T) -> Row {
+    Row {
+        prefix: String::new(),
+        name: root.name().clone(),
+        is_file: root.is_file(),
+        size: root.size(),
+        parent_size: root.size(),
+        root_size: root.size(),
+        note: None,
+    }
+}
+
+fn add_tree_rows<T: DisplayableEntry>(entry: &T,
+                                      options: &Options,
+                                      level: usize,
+                                      prefix: String,
+                                      continuation: String,
+                                      parent_size: u64,
+                                      root_size: u64,
+                                      rows: &mut Vec<Row>) {
+    rows.push(Row {
+        prefix: prefix,
+        name: entry.name().clone(),
+        is_file: entry.is_file(),
+        size: entry.size(),
+        parent_size: parent_size,
+        root_size: root_size,
+        note: archive_note(entry, options),
+    });
+
     if options.depth_accepts(level) {
-        let mut shown_entries = 0;
+        let style = options.tree_style();
+        // Collect the children that will be shown first, so we know which one is the last one
+        // after hidden entries and the limit have been taken into account.
+        let children = visible_children(entry, options);
+        let last_index = children.len().saturating_sub(1);
 
-        for child in entry.children_iter() {
-            if !options.should_show_hidden() && child.is_hidden() {
-                continue;
-            }
+        for (index, child) in children.into_iter().enumerate() {
+            let is_last = index == last_index;
+            add_tree_rows(
+                child,
+                options,
+                level + 1,
+                continuation.clone() + style.branch(is_last),
+                continuation.clone() + style.continuation(is_last),
+                entry.size(),
+                root_size,
+                rows
+            );
+        }
+    }
+}
+
+pub fn visible_children<'a, T: DisplayableEntry>(entry: &'a T, options: &Options)
+                                                 -> Vec<&'a T::Child> {
+    let mut children = Vec::new();
+
+    for child in entry.children_iter() {
+        if options.limit_reached(children.len()) {
+            break;
+        }
 
-            print_indented_tree(child, options, level + 1);
-            shown_entries += 1;
+        let is_shown = options.should_show_hidden() || !child.is_hidden();
+        if is_shown && options.is_large_enough(child.size()) {
+            children.push(child);
+        }
+    }
+
+    children
+}
 
-            if options.limit_reached(shown_entries) {
-                break;
+fn format_rows(rows: &[Row],
+               style: &TreeStyle,
+               options: &Options,
+               palette: &Palette,
+               terminal_width: Option<usize>) -> Vec<String> {
+    let show_percent = options.should_show_percent();
+    let show_bar = options.should_show_bar();
+    let sizes: Vec<String> = rows.iter()
+        .map(|row| row.size.as_size_display(options.size_format()))
+        .collect();
+    let notes: Vec<String> = rows.iter()
+        .map(|row| row.note.clone().unwrap_or_default())
+        .collect();
+
+    if style == &TreeStyle::Indent && !show_percent && !show_bar {
+        return rows.iter().zip(&sizes).zip(&notes).map(|((row, size), note)| {
+            let mut line = format!("{}{} {}",
+                                   row.prefix,
+                                   palette.paint_name(&row.name, row.is_file),
+                                   palette.paint_size(row.size, size));
+            if !note.is_empty() {
+                line.push_str(" ");
+                line.push_str(note);
             }
+            line
+        }).collect();
+    }
+
+    // Everything else gets the sizes right-aligned in a column after the longest name, followed
+    // by the other columns.
+    let width = |row: &Row| row.prefix.chars().count() + row.name.chars().count();
+    let size_width = sizes.iter().map(|size| size.len()).max().unwrap_or(0);
+    let mut name_width = rows.iter().map(&width).max().unwrap_or(0);
+
+    let note_width = notes.iter().map(|note| note.chars().count()).max().unwrap_or(0);
+    let mut fixed_width = 2 + size_width;
+    if note_width > 0 {
+        fixed_width += 2 + note_width;
+    }
+    if show_percent {
+        fixed_width += 2 * (1 + PERCENT_WIDTH);
+    }
+
+    let bar_width = match terminal_width {
+        Some(columns) if show_bar => {
+            let room = columns.saturating_sub(name_width + fixed_width + 3);
+            room.max(MIN_BAR_WIDTH).min(MAX_BAR_WIDTH)
+        },
+        _ => DEFAULT_BAR_WIDTH,
+    };
+    if show_bar {
+        fixed_width += 3 + bar_width;
+    }
+
+    // When it still does not fit, names are truncated. Names are never made shorter than
+    // MIN_NAME_WIDTH, so on really narrow terminals the lines will wrap.
+    if let Some(columns) = terminal_width {
+        if name_width + fixed_width > columns {
+            name_width = columns.saturating_sub(fixed_width).max(MIN_NAME_WIDTH);
         }
     }
+
+    rows.iter().zip(&sizes).zip(&notes).map(|((row, size), note)| {
+        let name = truncate(&row.name, name_width.saturating_sub(row.prefix.chars().count()));
+        let padding = name_width.saturating_sub(row.prefix.chars().count() + name.chars().count());
+        // Padding is added outside of the colors, since escape codes take no room on screen.
+        let mut line = format!("{}{}{}  {}{}",
+                               row.prefix,
+                               palette.paint_name(&name, row.is_file),
+                               " ".repeat(padding),
+                               " ".repeat(size_width - size.len()),
+                               palette.paint_size(row.size, size));
+
+        if note_width > 0 {
+            line.push_str(&format!("  {:<width$}", note, width = note_width));
+        }
+
+        if show_percent {
+            line.push_str(&format!(" {:>6.1}%", percentage(row.size, row.parent_size)));
+            line.push_str(&format!(" {:>6.1}%", percentage(row.size, row.root_size)));
+        }
+
+        if show_bar {
+            line.push_str(" ");
+            line.push_str(&bar(row.size, row.parent_size, bar_width));
+        }
+
+        line
+    }).collect()
 }
 
-fn print_largest_files_in_directory(root: Root, options: &Options) {
-    let mut files = files_in(root.entry(), !options.should_show_hidden());
-    let mut shown_files = 0;
+fn percentage(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        100.0 * part as f64 / whole as f64
+    }
+}
+
+pub fn bar(part: u64, whole: u64, width: usize) -> String {
+    let filled = ((percentage(part, whole) / 100.0) * width as f64).round() as usize;
+    let filled = filled.min(width);
+    format!("[{}{}]", "#".repeat(filled), " ".repeat(width - filled))
+}
+
+// Archives show how much what is in them takes up when unpacked, and what is in archives shows
+// how much of the archive it takes up.
+fn archive_note<T: DisplayableEntry>(entry: &T, options: &Options) -> Option<String> {
+    let format = options.size_format();
+    match (entry.uncompressed_size(), entry.compressed_size()) {
+        (Some(size), _) => Some(format!("({} uncompressed)", size.as_size_display(format))),
+        (None, Some(size)) => Some(format!("({} compressed)", size.as_size_display(format))),
+        (None, None) => None,
+    }
+}
+
+// Paths of archives and what is in them cannot be used like other paths in other commands, since
+// archives are files and what is in them is not on disk by itself, so they are marked.
+fn path_note(entry: &Entry, options: &Options) -> Option<String> {
+    let format = options.size_format();
+    match (entry.uncompressed_size(), entry.compressed_size()) {
+        (Some(size), _) => {
+            Some(format!("(archive, {} uncompressed)", size.as_size_display(format)))
+        },
+        (None, Some(size)) => {
+            Some(format!("(in archive, {} compressed)", size.as_size_display(format)))
+        },
+        (None, None) => None,
+    }
+}
+
+fn count_text(count: u64) -> String {
+    if count == 1 {
+        "(1 file)".to_string()
+    } else {
+        format!("({} files)", count)
+    }
+}
+
+pub fn truncate(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        name.to_string()
+    } else if width == 0 {
+        String::new()
+    } else {
+        let mut truncated: String = name.chars().take(width - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn largest_file_rows<F: FileSystem>(fs: &F, root: &Root, options: &Options) -> Vec<Row> {
+    let prefix = path_prefix(fs, root, options);
+    let mut files = files_in(root.entry(), &prefix, root.size(), !options.should_show_hidden());
+    let mut rows = vec![root_row(root)];
 
     files.sort_by( |a, b| {
         // Note: We change the ordering to get in descending order
-        b.size().cmp(&a.size())
+        b.entry.size().cmp(&a.entry.size())
     });
 
-    println!("{}", root);
-    for file in files {
-        println!("  {}", file);
+    for file in files.into_iter().filter(|file| options.is_large_enough(file.entry.size())) {
+        rows.push(Row {
+            prefix: "  ".to_string(),
+            name: file.path,
+            is_file: true,
+            size: file.entry.size(),
+            parent_size: file.parent_size,
+            root_size: root.size(),
+            note: path_note(file.entry, options),
+        });
 
-        shown_files += 1;
-        if options.limit_reached(shown_files) {
+        if options.limit_reached(rows.len() - 1) {
             break;
         }
     }
+
+    rows
 }
 
-fn files_in(entry: &Entry, skip_hidden: bool) -> Vec<&Entry> {
+// Found entries are shown with their path relative to the root, or with an absolute path when
+// asked for. Either way the paths can be used as-is in other commands.
+fn path_prefix<F: FileSystem>(fs: &F, root: &Root, options: &Options) -> String {
+    if !options.should_show_absolute_paths() {
+        return String::new();
+    }
+
+    let path = root.path();
+    let absolute = fs.canonicalize(&path).unwrap_or_else(|_| {
+        env::current_dir().map(|current| current.join(&path)).unwrap_or(path.clone())
+    });
+    let absolute = absolute.to_string_lossy().into_owned();
+
+    if absolute.ends_with('/') { absolute } else { absolute + "/" }
+}
+
+struct FoundFile<'a> {
+    entry: &'a Entry,
+    path: String,
+    parent_size: u64,
+}
+
+// The path is the one of the parent directory, including the trailing slash.
+fn files_in<'a>(entry: &'a Entry,
+                path: &str,
+                parent_size: u64,
+                skip_hidden: bool) -> Vec<FoundFile<'a>> {
     if entry.is_file() {
         if entry.is_hidden() && skip_hidden {
             vec![]
         } else {
-            vec![entry]
+            let path = format!("{}{}", path, entry.name());
+            vec![FoundFile { entry: entry, path: path, parent_size: parent_size }]
+        }
+    } else {
+        entry.children_iter()
+            .flat_map(|child| {
+                let child_path = if child.is_file() {
+                    path.to_string()
+                } else {
+                    format!("{}{}", path, child.name())
+                };
+                files_in(child, &child_path, entry.size(), skip_hidden)
+            })
+            .collect()
+    }
+}
+
+fn write_breakdown<F, W>(root: Root, options: &Options, key: F, out: &mut W) -> io::Result<()>
+    where F: Fn(&Entry) -> String, W: Write {
+    let rows = breakdown_rows(&root, options, &key);
+    write_rows(&rows, &TreeStyle::Indent, options, out)
+}
+
+// Files are grouped for the whole root, or for every top-level child on its own when asked to.
+fn breakdown_rows<F>(root: &Root, options: &Options, key: &F) -> Vec<Row>
+    where F: Fn(&Entry) -> String {
+    let mut rows = vec![root_row(root)];
+
+    if options.should_group_per_child() {
+        for child in visible_children(root, options) {
+            rows.push(Row {
+                prefix: "  ".to_string(),
+                name: child.name().clone(),
+                is_file: child.is_file(),
+                size: child.size(),
+                parent_size: root.size(),
+                root_size: root.size(),
+                note: None,
+            });
+            add_group_rows(breakdown::group_files(child, key), "    ", child.size(), root.size(),
+                           options, &mut rows);
         }
     } else {
-        entry.children_iter().flat_map(|child| files_in(child, skip_hidden)).collect()
+        add_group_rows(breakdown::group_files(root.entry(), key), "  ", root.size(), root.size(),
+                       options, &mut rows);
+    }
+
+    rows
+}
+
+fn add_group_rows(groups: Vec<Group>,
+                  prefix: &str,
+                  parent_size: u64,
+                  root_size: u64,
+                  options: &Options,
+                  rows: &mut Vec<Row>) {
+    let groups = groups.into_iter().filter(|group| options.is_large_enough(group.size));
+
+    for (index, group) in groups.enumerate() {
+        if options.limit_reached(index) {
+            break;
+        }
+
+        rows.push(Row {
+            prefix: prefix.to_string(),
+            name: group.name,
+            is_file: true,
+            size: group.size,
+            parent_size: parent_size,
+            root_size: root_size,
+            note: Some(count_text(group.count)),
+        });
+    }
+}
+
+fn write_ages<W: Write>(root: Root, options: &Options, out: &mut W) -> io::Result<()> {
+    let rows = age_rows(&root, options, utils::now());
+    write_rows(&rows, &TreeStyle::Indent, options, out)
+}
+
+fn age_rows(root: &Root, options: &Options, now: i64) -> Vec<Row> {
+    let groups = |entry: &Entry, _: &Path| {
+        breakdown::group_by_age(entry, options.age_buckets(), options.timestamp(), now)
+    };
+    let mut rows = vec![root_row(root)];
+    add_split_rows(root.entry(), Path::new(""), options, &groups, 0, "  ", root.size(), &mut rows);
+    rows
+}
+
+// Work trees are looked for from the real path of the root, so they are also found above it.
+fn write_git<F: FileSystem, W: Write>(fs: &F, root: Root, options: &Options, out: &mut W)
+                                      -> io::Result<()> {
+    let rows = git_rows(fs, &root, options);
+    write_rows(&rows, &TreeStyle::Indent, options, out)
+}
+
+// The work trees the root is in are found from its full path.
+fn git_rows<F: FileSystem>(fs: &F, root: &Root, options: &Options) -> Vec<Row> {
+    let path = Path::new(root.name());
+    let path = fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let splits = git::classify(fs, &path, root.entry());
+
+    let groups = |_: &Entry, path: &Path| splits.get(path).cloned().unwrap_or_default();
+    let mut rows = vec![root_row(root)];
+    add_split_rows(root.entry(), Path::new(""), options, &groups, 0, "  ", root.size(), &mut rows);
+    rows
+}
+
+// Every directory in the tree gets the groups its files are split in listed first, in brackets,
+// followed by its children. Directories are given to the groups with their path from the root.
+fn add_split_rows<F>(entry: &Entry,
+                     path: &Path,
+                     options: &Options,
+                     groups: &F,
+                     level: usize,
+                     prefix: &str,
+                     root_size: u64,
+                     rows: &mut Vec<Row>) where F: Fn(&Entry, &Path) -> Vec<Group> {
+    let groups_of_entry = groups(entry, path);
+    for group in groups_of_entry.into_iter().filter(|group| options.is_large_enough(group.size)) {
+        rows.push(Row {
+            prefix: prefix.to_string(),
+            name: format!("[{}]", group.name),
+            is_file: true,
+            size: group.size,
+            parent_size: entry.size(),
+            root_size: root_size,
+            note: Some(count_text(group.count)),
+        });
+    }
+
+    if !options.depth_accepts(level) {
+        return;
+    }
+
+    for child in visible_children(entry, options) {
+        rows.push(Row {
+            prefix: prefix.to_string(),
+            name: child.name().clone(),
+            is_file: child.is_file(),
+            size: child.size(),
+            parent_size: entry.size(),
+            root_size: root_size,
+            note: None,
+        });
+
+        // Archives are in the groups of their directory, so what is in them is not split again.
+        if !child.is_file() && !child.is_archive() {
+            let prefix = format!("{}  ", prefix);
+            let path = path.join(child.name().trim_end_matches('/'));
+            add_split_rows(child, &path, options, groups, level + 1, &prefix, root_size, rows);
+        }
+    }
+}
+
+fn write_duplicates<F: FileSystem, W: Write>(fs: &F, root: Root, options: &Options, out: &mut W)
+                                             -> io::Result<()> {
+    let rows = duplicate_rows(fs, &root, options);
+    write_rows(&rows, &TreeStyle::Indent, options, out)
+}
+
+// Every set of duplicates is shown with the room wasted by the extra copies, followed by the files
+// in it.
+fn duplicate_rows<F: FileSystem>(fs: &F, root: &Root, options: &Options) -> Vec<Row> {
+    let prefix = path_prefix(fs, root, options);
+    let mut rows = vec![root_row(root)];
+
+    for (index, set) in duplicates::find_duplicates(fs, root, options).into_iter().enumerate() {
+        if options.limit_reached(index) {
+            break;
+        }
+
+        rows.push(Row {
+            prefix: "  ".to_string(),
+            name: format!("[wasted by {} copies]", set.paths.len()),
+            is_file: false,
+            size: set.wasted(),
+            parent_size: root.size(),
+            root_size: root.size(),
+            note: None,
+        });
+
+        for path in set.paths.iter() {
+            rows.push(Row {
+                prefix: "    ".to_string(),
+                name: format!("{}{}", prefix, path.to_string_lossy()),
+                is_file: true,
+                size: set.size,
+                parent_size: set.size * set.paths.len() as u64,
+                root_size: root.size(),
+                note: None,
+            });
+        }
+    }
+
+    rows
+}
+
+fn write_empty<F: FileSystem, W: Write>(fs: &F, root: Root, options: &Options, out: &mut W)
+                                       -> io::Result<()> {
+    let groups = empty::find_empty(&root, options);
+
+    if options.should_separate_with_nul() {
+        let prefix = path_prefix(fs, &root, options);
+        for path in groups.iter().flat_map(|group| group.paths()) {
+            write!(out, "{}{}\0", prefix, path.to_string_lossy())?;
+        }
+        return Ok(());
+    }
+
+    write_rows(&empty_rows(fs, &root, &groups, options), &TreeStyle::Indent, options, out)?;
+
+    let (directories, files) = empty::count(&groups);
+    writeln!(out, "{} {}, {} {}",
+             directories,
+             if directories == 1 { "empty directory" } else { "empty directories" },
+             files,
+             if files == 1 { "zero-byte file" } else { "zero-byte files" })
+}
+
+fn write_cleanable<F: FileSystem, W: Write>(fs: &F, root: Root, options: &Options, out: &mut W)
+                                           -> io::Result<()> {
+    let found = cleanable::find_cleanable(root.entry(), options.cleanable_rules());
+    let rows = cleanable_rows(fs, &root, &found, options);
+    write_rows(&rows, &TreeStyle::Indent, options, out)?;
+
+    let total: u64 = found.iter().map(|cleanable| cleanable.size).sum();
+    writeln!(out, "{} reclaimable in {} {}",
+             total.as_size_display(options.size_format()),
+             found.len(),
+             if found.len() == 1 { "directory" } else { "directories" })
+}
+
+// Directories that match a rule with markers show the marker they were found next to.
+fn cleanable_rows<F: FileSystem>(fs: &F,
+                                 root: &Root,
+                                 found: &[Cleanable],
+                                 options: &Options) -> Vec<Row> {
+    let prefix = path_prefix(fs, root, options);
+    let mut rows = vec![root_row(root)];
+
+    for cleanable in found {
+        rows.push(Row {
+            prefix: "  ".to_string(),
+            name: format!("{}{}/", prefix, cleanable.path.to_string_lossy()),
+            is_file: false,
+            size: cleanable.size,
+            parent_size: root.size(),
+            root_size: root.size(),
+            note: cleanable.marker.as_ref().map(|marker| format!("(next to {})", marker)),
+        });
+    }
+
+    rows
+}
+
+// Empty entries are listed below their parent directory, unless they are directly inside of the
+// root.
+fn empty_rows<F: FileSystem>(fs: &F,
+                             root: &Root,
+                             groups: &[EmptyGroup],
+                             options: &Options) -> Vec<Row> {
+    let prefix = path_prefix(fs, root, options);
+    let mut rows = vec![root_row(root)];
+
+    for group in groups {
+        let indentation = if group.parent == Path::new("") {
+            "  "
+        } else {
+            rows.push(Row {
+                prefix: "  ".to_string(),
+                name: format!("{}{}/", prefix, group.parent.to_string_lossy()),
+                is_file: false,
+                size: group.parent_entry.size(),
+                parent_size: root.size(),
+                root_size: root.size(),
+                note: None,
+            });
+            "    "
+        };
+
+        for entry in group.entries.iter() {
+            rows.push(Row {
+                prefix: indentation.to_string(),
+                name: entry.name().clone(),
+                is_file: entry.is_file(),
+                size: entry.size(),
+                parent_size: group.parent_entry.size(),
+                root_size: root.size(),
+                note: None,
+            });
+        }
+    }
+
+    rows
+}
+
+struct FoundDirectory<'a> {
+    entry: &'a Entry,
+    path: String,
+    parent_size: u64,
+}
+
+// Directories are ranked by the size of the files directly inside of them, or by their total
+// size when --cumulative is given. The root itself is not ranked since it is already shown above
+// the results.
+fn largest_directory_rows<F: FileSystem>(fs: &F, root: &Root, options: &Options) -> Vec<Row> {
+    let prefix = path_prefix(fs, root, options);
+    let mut directories = Vec::new();
+    directories_in(root.entry(), &prefix, !options.should_show_hidden(), &mut directories);
+
+    let ranked_size = |directory: &FoundDirectory| {
+        if options.should_rank_cumulative() {
+            directory.entry.size()
+        } else {
+            own_file_size(directory.entry)
+        }
+    };
+
+    directories.sort_by( |a, b| {
+        // Note: We change the ordering to get in descending order
+        ranked_size(b).cmp(&ranked_size(a))
+    });
+
+    let mut rows = vec![root_row(root)];
+    for directory in directories.iter().filter(|directory| {
+        options.is_large_enough(ranked_size(directory))
+    }) {
+        if options.limit_reached(rows.len() - 1) {
+            break;
+        }
+
+        rows.push(Row {
+            prefix: "  ".to_string(),
+            name: directory.path.clone(),
+            is_file: false,
+            size: ranked_size(directory),
+            parent_size: directory.parent_size,
+            root_size: root.size(),
+            note: path_note(directory.entry, options),
+        });
+    }
+
+    rows
+}
+
+// Hidden directories are skipped together with everything inside of them, like in the tree.
+fn directories_in<'a>(entry: &'a Entry,
+                      path: &str,
+                      skip_hidden: bool,
+                      found: &mut Vec<FoundDirectory<'a>>) {
+    for child in entry.children_iter() {
+        if child.is_file() || (child.is_hidden() && skip_hidden) {
+            continue;
+        }
+
+        // Directory names already end with a slash.
+        let child_path = format!("{}{}", path, child.name());
+        directories_in(child, &child_path, skip_hidden, found);
+        found.push(FoundDirectory { entry: child, path: child_path, parent_size: entry.size() });
+    }
+}
+
+fn own_file_size(entry: &Entry) -> u64 {
+    entry.children_iter()
+        .filter(|child| child.is_file())
+        .map(|child| child.size())
+        .fold(0, |sum, size| sum + size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archives::tests::tar;
+    use filesystem::{MemoryFileSystem, RealFileSystem};
+    use options::{Depth, Limit, OptionsBuilder};
+    use root::Root;
+    use std::path::Path;
+    use utils::tests;
+
+    fn tree_lines(path: &str, options: OptionsBuilder) -> Vec<String> {
+        let options = options.build();
+        let root = Root::for_path(Path::new(path)).unwrap();
+        tree_lines_for(root, &options)
+    }
+
+    fn tree_lines_for(root: Root, options: &Options) -> Vec<String> {
+        let mut rows = Vec::new();
+        let size = root.size();
+        add_tree_rows(&root, options, 0, String::new(), String::new(), size, size, &mut rows);
+        format_rows(&rows, options.tree_style(), options, &Palette::disabled(), Some(200))
+    }
+
+    // A small project in memory, so the output can be compared exactly.
+    fn project() -> Root {
+        Root::for_path_in(&project_fs(), Path::new("project")).unwrap()
+    }
+
+    fn project_fs() -> MemoryFileSystem {
+        MemoryFileSystem::new()
+            .file("project/src/main.rs", 3000)
+            .file("project/src/lib.rs", 1000)
+            .file("project/src/bin/tool.rs", 200)
+            .file("project/README", 500)
+            .file("project/.git/objects/pack", 8000)
+            .file("project/.git/HEAD", 20)
+            .unreadable("project/private")
+            .file("project/private/key", 100)
+    }
+
+    fn project_lines(options: OptionsBuilder) -> Vec<String> {
+        let options = options.size_format(tests::bytes()).build();
+        match *options.mode() {
+            Mode::Files => {
+                let rows = largest_file_rows(&project_fs(), &project(), &options);
+                format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), Some(200))
+            },
+            _ => tree_lines_for(project(), &options),
+        }
+    }
+
+    #[test]
+    fn it_shows_what_is_in_archives_with_their_other_size() {
+        let archive = tar(&[("docs/a.txt", &[b'a'; 600]), ("b", b"b")]);
+        let fs = MemoryFileSystem::new().contents("project/backup.tar", archive);
+        let root = Root::scan_path(&fs, Path::new("project"), true).unwrap();
+        let options = Options::builder()
+            .size_format(tests::bytes())
+            .limit(Limit::Unlimited)
+            .depth(Depth::Unlimited)
+            .build();
+
+        assert_eq!(tree_lines_for(root, &options), vec![
+            "project/ 3584 B",
+            "  backup.tar/ 3584 B (601 B uncompressed)",
+            "    docs/ 600 B (1536 B compressed)",
+            "      a.txt 600 B (1536 B compressed)",
+            "    b 1 B (1024 B compressed)",
+        ]);
+    }
+
+    #[test]
+    fn it_marks_the_paths_of_archives_and_what_is_in_them() {
+        let archive = tar(&[("docs/a.txt", &[b'a'; 600]), ("b", b"b")]);
+        let fs = MemoryFileSystem::new()
+            .contents("project/backup.tar", archive)
+            .file("project/src/main.rs", 100);
+        let root = Root::scan_path(&fs, Path::new("project"), true).unwrap();
+
+        let options = Options::builder()
+            .mode(Mode::Files)
+            .size_format(tests::bytes())
+            .limit(Limit::Unlimited)
+            .build();
+        let rows = largest_file_rows(&fs, &root, &options);
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(lines, vec![
+            "project/ 3684 B",
+            "  backup.tar/docs/a.txt 600 B (in archive, 1536 B compressed)",
+            "  src/main.rs 100 B",
+            "  backup.tar/b 1 B (in archive, 1024 B compressed)",
+        ]);
+
+        let options = Options::builder()
+            .mode(Mode::Dirs)
+            .size_format(tests::bytes())
+            .limit(Limit::Unlimited)
+            .build();
+        let rows = largest_directory_rows(&fs, &root, &options);
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(lines, vec![
+            "project/ 3684 B",
+            "  backup.tar/docs/ 600 B (in archive, 1536 B compressed)",
+            "  src/ 100 B",
+            "  backup.tar/ 1 B (archive, 601 B uncompressed)",
+        ]);
+    }
+
+    #[test]
+    fn it_shows_the_largest_visible_child_by_default() {
+        assert_eq!(project_lines(Options::builder()), vec!["project/ 12720 B", "  src/ 4200 B"]);
+    }
+
+    #[test]
+    fn it_shows_everything_with_all_and_no_limits() {
+        let options = Options::builder()
+            .show_hidden(true)
+            .limit(Limit::Unlimited)
+            .depth(Depth::Unlimited);
+        assert_eq!(project_lines(options), vec![
+            "project/ 12720 B",
+            "  .git/ 8020 B",
+            "    objects/ 8000 B",
+            "      pack 8000 B",
+            "    HEAD 20 B",
+            "  src/ 4200 B",
+            "    main.rs 3000 B",
+            "    lib.rs 1000 B",
+            "    bin/ 200 B",
+            "      tool.rs 200 B",
+            "  README 500 B",
+            "  private/ 0 B",
+        ]);
+    }
+
+    #[test]
+    fn it_applies_the_limit_to_every_directory() {
+        let options = Options::builder().limit(Limit::Limited(2)).depth(Depth::Limited(2));
+        assert_eq!(project_lines(options), vec![
+            "project/ 12720 B",
+            "  src/ 4200 B",
+            "    main.rs 3000 B",
+            "    lib.rs 1000 B",
+            "  README 500 B",
+        ]);
+    }
+
+    #[test]
+    fn it_shows_the_largest_files_of_a_tree() {
+        let options = Options::builder()
+            .mode(Mode::Files)
+            .limit(Limit::Limited(4))
+            .show_hidden(true);
+        assert_eq!(project_lines(options), vec![
+            "project/ 12720 B",
+            "  .git/objects/pack 8000 B",
+            "  src/main.rs 3000 B",
+            "  src/lib.rs 1000 B",
+            "  README 500 B",
+        ]);
+        let options = Options::builder()
+            .mode(Mode::Files)
+            .show_hidden(true)
+            .limit(Limit::Unlimited)
+            .min_size(1001);
+        assert_eq!(project_lines(options), vec![
+            "project/ 12720 B",
+            "  .git/objects/pack 8000 B",
+            "  src/main.rs 3000 B",
+        ]);
+    }
+
+    #[test]
+    fn it_indents_children_with_two_spaces_by_default() {
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let lines = tree_lines("./src", Options::builder());
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], format!("{}", root));
+        assert!(lines[1].starts_with("  "));
+        assert!(!lines[1].starts_with("   "));
+    }
+
+    #[test]
+    fn it_draws_unicode_connectors() {
+        let options = Options::builder().limit(Limit::Unlimited).tree_style(TreeStyle::Unicode);
+        let lines = tree_lines("./src", options);
+        let (last, middle) = lines[1..].split_last().unwrap();
+
+        assert!(lines.len() > 2);
+        assert!(middle.iter().all(|line| line.starts_with("├── ")));
+        assert!(last.starts_with("└── "));
+    }
+
+    #[test]
+    fn it_draws_ascii_connectors() {
+        let options = Options::builder().limit(Limit::Unlimited).tree_style(TreeStyle::Ascii);
+        let lines = tree_lines("./src", options);
+        let (last, middle) = lines[1..].split_last().unwrap();
+
+        assert!(middle.iter().all(|line| line.starts_with("|-- ")));
+        assert!(last.starts_with("`-- "));
+    }
+
+    #[test]
+    fn it_detects_the_last_child_when_the_limit_is_reached() {
+        let options = Options::builder().limit(Limit::Limited(2)).tree_style(TreeStyle::Unicode);
+        let lines = tree_lines("./src", options);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("├── "));
+        assert!(lines[2].starts_with("└── "));
+    }
+
+    #[test]
+    fn it_detects_the_last_child_when_hidden_entries_are_skipped() {
+        let options = Options::builder().limit(Limit::Unlimited).tree_style(TreeStyle::Unicode);
+        let lines = tree_lines(".", options);
+
+        assert!(lines.last().unwrap().starts_with("└── "));
+        assert!(!lines.iter().any(|line| line.contains(".gitignore")));
+    }
+
+    #[test]
+    fn it_continues_vertical_lines_for_descendants() {
+        let options = Options::builder()
+            .depth(Depth::Limited(2))
+            .limit(Limit::Limited(2))
+            .tree_style(TreeStyle::Unicode)
+            .show_hidden(true);
+        let lines = tree_lines(".", options);
+
+        assert!(lines.iter().any(|line| line.starts_with("│   ├── ") ||
+                                       line.starts_with("│   └── ") ||
+                                       line.starts_with("    └── ")));
+    }
+
+    #[test]
+    fn it_right_aligns_sizes_in_drawn_trees() {
+        let options = Options::builder().limit(Limit::Unlimited).tree_style(TreeStyle::Unicode);
+        let lines = tree_lines("./src", options);
+        let length = lines[0].chars().count();
+
+        assert!(lines.iter().all(|line| line.chars().count() == length));
+    }
+
+    #[test]
+    fn it_shows_percentages_of_parent_and_root() {
+        let options = Options::builder().limit(Limit::Unlimited).show_percent(true);
+        let lines = tree_lines("./src", options);
+
+        assert!(lines[0].ends_with("  100.0%  100.0%"));
+        assert!(lines[1..].iter().all(|line| line.ends_with("%")));
+    }
+
+    #[test]
+    fn it_scales_percentages_and_bars() {
+        assert_eq!(percentage(1, 4), 25.0);
+        assert_eq!(percentage(1, 0), 0.0);
+        assert_eq!(bar(1, 4, 8), "[##      ]");
+        assert_eq!(bar(4, 4, 4), "[####]");
+        assert_eq!(bar(0, 4, 4), "[    ]");
+    }
+
+    #[test]
+    fn it_adapts_the_bar_to_the_terminal_width() {
+        let options = Options::builder().show_bar(true).build();
+        let rows = vec![Row {
+            prefix: String::new(),
+            name: "name".to_string(),
+            is_file: true,
+            size: 10,
+            parent_size: 10,
+            root_size: 10,
+            note: None,
+        }];
+
+        let wide = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(),
+                               Some(200));
+        let narrow = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(),
+                                 Some(30));
+        let unknown = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(),
+                                  None);
+
+        assert_eq!(wide, vec![format!("name  10 B [{}]", "#".repeat(MAX_BAR_WIDTH))]);
+        assert_eq!(narrow, vec!["name  10 B [#################]"]);
+        assert_eq!(unknown, vec![format!("name  10 B [{}]", "#".repeat(DEFAULT_BAR_WIDTH))]);
+    }
+
+    #[test]
+    fn it_truncates_names_that_do_not_fit_the_terminal() {
+        let options = Options::builder().show_bar(true).show_percent(true).build();
+        let rows = vec![Row {
+            prefix: String::new(),
+            name: "a-really-long-file-name-that-goes-on.txt".to_string(),
+            is_file: true,
+            size: 10,
+            parent_size: 10,
+            root_size: 10,
+            note: None,
+        }];
+
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(),
+                                Some(50));
+        assert!(lines[0].starts_with("a-really-long-…  10 B"));
+        assert_eq!(lines[0].chars().count(), 50);
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("longer", 4), "lon…");
+    }
+
+    #[test]
+    fn it_keeps_columns_aligned_when_colored() {
+        let options = Options::builder().show_percent(true).build();
+        let palette = Palette::from_ls_colors("di=01;34:*.rs=32");
+        let rows = vec![
+            Row {
+                prefix: String::new(),
+                name: "src/".to_string(),
+                is_file: false,
+                size: 2_000,
+                parent_size: 2_000,
+                root_size: 2_000,
+                note: None,
+            },
+            Row {
+                prefix: "  ".to_string(),
+                name: "main.rs".to_string(),
+                is_file: true,
+                size: 20,
+                parent_size: 2_000,
+                root_size: 2_000,
+                note: None,
+            },
+        ];
+
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &palette, None);
+        assert_eq!(lines, vec![
+            "\x1b[01;34msrc/\x1b[0m       \x1b[32m2.00 kB\x1b[0m  100.0%  100.0%",
+            "  \x1b[32mmain.rs\x1b[0m     20 B    1.0%    1.0%",
+        ]);
+    }
+
+    fn directory_lines(path: &str, options: OptionsBuilder) -> Vec<String> {
+        let options = options.build();
+        let root = Root::for_path(Path::new(path)).unwrap();
+
+        let rows = largest_directory_rows(&RealFileSystem, &root, &options);
+        format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), Some(200))
+    }
+
+    #[test]
+    fn it_ranks_directories_by_their_own_files_or_cumulative_size() {
+        use std::fs;
+        use utils::tests::TempDir;
+
+        let base = TempDir::new("dirs");
+        fs::create_dir_all(base.join("many/small")).unwrap();
+        fs::create_dir_all(base.join("big")).unwrap();
+        fs::create_dir_all(base.join(".hidden")).unwrap();
+        fs::write(base.join("big/file"), vec![0u8; 3000]).unwrap();
+        fs::write(base.join(".hidden/file"), vec![0u8; 20000]).unwrap();
+        for index in 0..5 {
+            fs::write(base.join(format!("many/small/{}", index)), vec![0u8; 700]).unwrap();
+        }
+        fs::write(base.join("many/file"), vec![0u8; 100]).unwrap();
+        let path = base.path().to_string_lossy().into_owned();
+
+        let options = Options::builder()
+            .mode(Mode::Dirs)
+            .limit(Limit::Unlimited)
+            .size_format(tests::bytes());
+        let own = directory_lines(&path, options);
+        assert_eq!(own.len(), 4);
+        assert_eq!(&own[1..], &[
+            "  many/small/ 3500 B".to_string(),
+            "  big/ 3000 B".to_string(),
+            "  many/ 100 B".to_string(),
+        ]);
+
+        let options = Options::builder()
+            .mode(Mode::Dirs)
+            .rank_cumulative(true)
+            .limit(Limit::Limited(2))
+            .show_hidden(true);
+        let cumulative = directory_lines(&path, options);
+        assert_eq!(cumulative.len(), 3);
+        assert!(cumulative[1].starts_with("  .hidden/ "));
+        assert!(cumulative[2].starts_with("  many/ "));
+    }
+
+    #[test]
+    fn it_shows_files_with_their_path_relative_to_the_root() {
+        let options = Options::builder().mode(Mode::Files).limit(Limit::Unlimited).build();
+        let root = Root::for_path(Path::new(".")).unwrap();
+        let rows = largest_file_rows(&RealFileSystem, &root, &options);
+
+        assert!(rows.iter().any(|row| row.name == "src/main.rs"));
+        assert!(rows.iter().any(|row| row.name == "LICENSE"));
+    }
+
+    #[test]
+    fn it_can_show_absolute_paths() {
+        let options = Options::builder()
+            .mode(Mode::Files)
+            .limit(Limit::Unlimited)
+            .show_absolute_paths(true)
+            .build();
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let rows = largest_file_rows(&RealFileSystem, &root, &options);
+
+        let expected = env::current_dir().unwrap().join("src/main.rs");
+        assert!(rows.iter().any(|row| Path::new(&row.name) == expected.as_path()));
+        assert!(rows.iter().skip(1).all(|row| row.name.starts_with("/")));
+    }
+
+    #[test]
+    fn it_makes_paths_absolute_in_the_file_system_of_the_root() {
+        // Paths in memory are already as absolute as they get, unlike in the current directory.
+        let fs = MemoryFileSystem::new()
+            .file("project/src/main.rs", 300)
+            .file("project/target/debug/app", 2000)
+            .file("project/Cargo.toml", 10);
+        let root = Root::for_path_in(&fs, Path::new("project")).unwrap();
+        let options = Options::builder()
+            .mode(Mode::Dirs)
+            .size_format(tests::bytes())
+            .show_absolute_paths(true)
+            .build();
+
+        let mut out = Vec::new();
+        options.mode().render(&fs, root, &options, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "project/ 2310 B\n  project/target/debug/ 2000 B\n");
+    }
+
+    #[test]
+    fn it_hides_entries_smaller_than_the_minimum_size() {
+        let options = Options::builder().show_hidden(true).limit(Limit::Unlimited).min_size(1024);
+        let lines = tree_lines(".", options);
+        assert!(lines.iter().any(|line| line.starts_with("  src/ ")));
+        assert!(!lines.iter().any(|line| line.starts_with("  .gitignore ")));
+    }
+
+    fn breakdown_lines(path: &str, options: OptionsBuilder) -> Vec<String> {
+        let options = options.build();
+        let root = Root::for_path(Path::new(path)).unwrap();
+
+        let rows = breakdown_rows(&root, &options, &breakdown::extension_key);
+        format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), Some(200))
+    }
+
+    #[test]
+    fn it_shows_the_size_and_count_of_groups() {
+        let options = Options::builder().mode(Mode::Extensions).limit(Limit::Limited(1));
+        let lines = breakdown_lines("./src", options);
+        let count = Root::for_path(Path::new("./src")).unwrap().children_iter().count() - 1;
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("  .rs "));
+        assert!(lines[1].ends_with(&format!(" ({} files)", count)));
+    }
+
+    #[test]
+    fn it_can_break_groups_down_per_top_level_child() {
+        let options = Options::builder()
+            .mode(Mode::Extensions)
+            .group_per_child(true)
+            .limit(Limit::Unlimited);
+        let lines = breakdown_lines(".", options);
+
+        let src = lines.iter().position(|line| line.starts_with("  src/ ")).unwrap();
+        assert!(lines[src + 1].starts_with("    .rs "));
+        assert!(lines.iter().any(|line| line.starts_with("    (no extension) ")));
+    }
+
+    #[test]
+    fn it_groups_files_by_owner() {
+        let options = Options::builder().mode(Mode::Owners).size_format(tests::bytes()).build();
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let uid = root.entry().uid();
+        let users = Accounts::users();
+
+        let rows = breakdown_rows(&root, &options, &|entry: &Entry| users.name(entry.uid()));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].name, users.name(uid));
+        assert_eq!(rows[1].note, Some(count_text(root.children_iter().count() as u64)));
+    }
+
+    #[test]
+    fn it_breaks_directories_down_by_git_status() {
+        let fs = MemoryFileSystem::new()
+            .contents("repo/.git/index", git::tests::index(&["src/main.rs"]))
+            .contents("repo/.gitignore", b"target/\n".to_vec())
+            .file("repo/src/main.rs", 200)
+            .file("repo/src/scratch.rs", 50)
+            .file("repo/target/app", 1000);
+        let root = Root::for_path_in(&fs, Path::new("repo")).unwrap();
+
+        let options = Options::builder()
+            .mode(Mode::Git)
+            .size_format(tests::bytes())
+            .limit(Limit::Unlimited)
+            .depth(Depth::Unlimited)
+            .build();
+        let rows = git_rows(&fs, &root, &options);
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(lines, vec![
+            "repo/ 1350 B",
+            "  [tracked] 200 B (1 file)",
+            "  [untracked] 58 B (2 files)",
+            "  [ignored] 1000 B (1 file)",
+            "  [.git] 92 B (1 file)",
+            "  target/ 1000 B",
+            "    [ignored] 1000 B (1 file)",
+            "    app 1000 B",
+            "  src/ 250 B",
+            "    [tracked] 200 B (1 file)",
+            "    [untracked] 50 B (1 file)",
+            "    main.rs 200 B",
+            "    scratch.rs 50 B",
+        ]);
+    }
+
+    #[test]
+    fn it_lists_duplicates_with_the_room_they_waste() {
+        let fs = MemoryFileSystem::new()
+            .contents("photos/2019/beach.jpg", b"sand and sea".to_vec())
+            .contents("photos/backup/beach.jpg", b"sand and sea".to_vec())
+            .contents("photos/backup/copy of beach.jpg", b"sand and sea".to_vec())
+            .contents("photos/notes.txt", b"beach".to_vec())
+            .contents("photos/backup/notes.txt", b"beach".to_vec())
+            .contents("photos/other.txt", b"other".to_vec());
+        let root = Root::for_path_in(&fs, Path::new("photos")).unwrap();
+
+        let options = Options::builder()
+            .mode(Mode::Duplicates)
+            .size_format(tests::bytes())
+            .limit(Limit::Unlimited)
+            .build();
+        let rows = duplicate_rows(&fs, &root, &options);
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(lines, vec![
+            "photos/ 51 B",
+            "  [wasted by 3 copies] 24 B",
+            "    2019/beach.jpg 12 B",
+            "    backup/beach.jpg 12 B",
+            "    backup/copy of beach.jpg 12 B",
+            "  [wasted by 2 copies] 5 B",
+            "    backup/notes.txt 5 B",
+            "    notes.txt 5 B",
+        ]);
+    }
+
+    #[test]
+    fn it_lists_cleanable_directories() {
+        let fs = MemoryFileSystem::new()
+            .file("code/app/Cargo.toml", 10)
+            .file("code/app/target/debug/app", 1000)
+            .file("code/site/node_modules/a/index.js", 300)
+            .file("code/notes/target/plan.txt", 40);
+        let root = Root::for_path_in(&fs, Path::new("code")).unwrap();
+
+        let options = Options::builder().mode(Mode::Cleanable).size_format(tests::bytes()).build();
+        let found = cleanable::find_cleanable(root.entry(), options.cleanable_rules());
+        let rows = cleanable_rows(&fs, &root, &found, &options);
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(lines, vec![
+            "code/ 1350 B",
+            "  app/target/ 1000 B (next to Cargo.toml)",
+            "  site/node_modules/ 300 B",
+        ]);
+    }
+
+    #[test]
+    fn it_breaks_directories_down_by_age() {
+        use std::fs::{self, File};
+        use std::time::{Duration, SystemTime};
+        use filters::Filter;
+        use utils::tests::TempDir;
+
+        let base = TempDir::new("ages");
+        fs::create_dir_all(base.join("logs")).unwrap();
+        fs::write(base.join("logs/new.log"), vec![0u8; 100]).unwrap();
+        fs::write(base.join("logs/old.log"), vec![0u8; 300]).unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(100 * utils::SECONDS_PER_DAY);
+        File::options().write(true).open(base.join("logs/old.log")).unwrap()
+            .set_modified(long_ago).unwrap();
+        let path = base.path().to_string_lossy().into_owned();
+
+        let options = Options::builder()
+            .mode(Mode::Ages)
+            .size_format(tests::bytes())
+            .depth(Depth::Limited(2))
+            .limit(Limit::Unlimited)
+            .build();
+        let root = Root::for_path(Path::new(&path)).unwrap();
+        let rows = age_rows(&root, &options, utils::now());
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(&lines[1..3], &[
+            "  [0-30 days] 100 B (1 file)",
+            "  [90-365 days] 300 B (1 file)",
+        ]);
+        assert!(lines[3].starts_with("  logs/ "));
+        assert_eq!(&lines[4..], &[
+            "    [0-30 days] 100 B (1 file)",
+            "    [90-365 days] 300 B (1 file)",
+            "    old.log 300 B",
+            "    new.log 100 B",
+        ]);
+
+        let options = Options::builder()
+            .older_than(30 * utils::SECONDS_PER_DAY)
+            .limit(Limit::Unlimited)
+            .depth(Depth::Unlimited)
+            .build();
+        let root = Filter::for_options(&options).apply(Root::for_path(Path::new(&path)).unwrap());
+        let lines = tree_lines_for(root, &options);
+        assert!(lines.iter().any(|line| line.trim_start().starts_with("old.log ")));
+        assert!(!lines.iter().any(|line| line.trim_start().starts_with("new.log ")));
+    }
+
+    #[test]
+    fn it_aligns_counts_in_a_column() {
+        let options = Options::builder().show_percent(true).build();
+        let rows = vec![
+            Row {
+                prefix: String::new(),
+                name: "./".to_string(),
+                is_file: false,
+                size: 200,
+                parent_size: 200,
+                root_size: 200,
+                note: None,
+            },
+            Row {
+                prefix: "  ".to_string(),
+                name: ".rs".to_string(),
+                is_file: true,
+                size: 100,
+                parent_size: 200,
+                root_size: 200,
+                note: Some("(1 file)".to_string()),
+            },
+        ];
+
+        let lines = format_rows(&rows, &TreeStyle::Indent, &options, &Palette::disabled(), None);
+        assert_eq!(lines, vec![
+            "./     200 B            100.0%  100.0%",
+            "  .rs  100 B  (1 file)   50.0%   50.0%",
+        ]);
+    }
+
+    #[test]
+    fn it_parses_tree_styles() {
+        assert_eq!("indent".parse::<TreeStyle>(), Ok(TreeStyle::Indent));
+        assert_eq!("unicode".parse::<TreeStyle>(), Ok(TreeStyle::Unicode));
+        assert_eq!("ascii".parse::<TreeStyle>(), Ok(TreeStyle::Ascii));
+        assert!("fancy".parse::<TreeStyle>().is_err());
+    }
+
+    #[test]
+    fn it_renders_into_a_writer_with_the_colors_it_is_given() {
+        let render = |options: Options| {
+            let mut out = Vec::new();
+            options.mode().render(&MemoryFileSystem::new(), project(), &options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let plain = render(Options::builder().size_format(tests::bytes()).build());
+        assert_eq!(plain, "project/ 12720 B\n  src/ 4200 B\n");
+
+        let colored = render(Options::builder()
+            .size_format(tests::bytes())
+            .palette(Palette::from_ls_colors("di=01;34"))
+            .build());
+        assert_eq!(colored, "\x1b[01;34mproject/\x1b[0m \x1b[32m12720 B\x1b[0m\n  \
+                             \x1b[01;34msrc/\x1b[0m \x1b[32m4200 B\x1b[0m\n");
     }
 }
@@ -0,0 +1,573 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use breakdown::AgeBuckets;
+use cleanable::{self, Rule};
+use colors::Palette;
+use entry::Timestamp;
+use formats::Format;
+use modes::{Mode, TreeStyle};
+use svg::{Coloring, Layout};
+use utils::SizeFormat;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Depth {
+    Unlimited,
+    Limited(usize)
+}
+
+impl Depth {
+    fn accepts(&self, level: usize) -> bool {
+        match *self {
+            Depth::Unlimited => true,
+            Depth::Limited(size) => size > level
+        }
+    }
+}
+
+impl FromStr for Depth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s == "all" { return Ok(Depth::Unlimited); }
+
+        let number = match s.parse::<usize>() {
+            Ok(number) => number,
+            Err(_) => return Err("Not a positive integer or \"all\"".to_string())
+        };
+
+        if number > 0 {
+            Ok(Depth::Limited(number))
+        } else {
+            Ok(Depth::Unlimited)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Limit {
+    Unlimited,
+    Limited(usize)
+}
+
+impl FromStr for Limit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s == "all" { return Ok(Limit::Unlimited); }
+
+        let number = match s.parse::<usize>() {
+            Ok(number) => number,
+            Err(_) => return Err("Not a positive integer or \"all\"".to_string())
+        };
+
+        if number > 0 {
+            Ok(Limit::Limited(number))
+        } else {
+            Ok(Limit::Unlimited)
+        }
+    }
+}
+
+// What to show and how. Options are put together with a builder, which starts out with what the
+// dutop command does when given no arguments and not printing to a terminal.
+pub struct Options {
+    roots: Vec<PathBuf>,
+    limit: Limit,
+    depth: Option<Depth>,
+    mode: Mode,
+    format: Format,
+    tree_style: TreeStyle,
+    svg_layout: Layout,
+    svg_coloring: Coloring,
+    svg_size: (usize, usize),
+    show_all: bool,
+    show_percent: bool,
+    show_bar: bool,
+    rank_cumulative: bool,
+    absolute_paths: bool,
+    group_per_child: bool,
+    min_size: u64,
+    owner: Option<u32>,
+    older_than: Option<u64>,
+    newer_than: Option<u64>,
+    timestamp: Timestamp,
+    nul_separated: bool,
+    cleanable_rules: Vec<Rule>,
+    save: Option<PathBuf>,
+    load: Option<PathBuf>,
+    diff: Option<PathBuf>,
+    watch: Option<Duration>,
+    cache: Option<PathBuf>,
+    into_archives: bool,
+    age_buckets: AgeBuckets,
+    allow_delete: bool,
+    palette: Palette,
+    width: Option<usize>,
+    size_format: SizeFormat,
+}
+
+impl Options {
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder {
+            options: Options {
+                roots: vec![PathBuf::from(".")],
+                limit: Limit::Limited(1),
+                depth: None,
+                mode: Mode::Tree,
+                format: Format::Text,
+                tree_style: TreeStyle::Indent,
+                svg_layout: Layout::Treemap,
+                svg_coloring: Coloring::Depth,
+                svg_size: (1024, 768),
+                show_all: false,
+                show_percent: false,
+                show_bar: false,
+                rank_cumulative: false,
+                absolute_paths: false,
+                group_per_child: false,
+                min_size: 0,
+                owner: None,
+                older_than: None,
+                newer_than: None,
+                timestamp: Timestamp::Modified,
+                nul_separated: false,
+                cleanable_rules: cleanable::default_rules(),
+                save: None,
+                load: None,
+                diff: None,
+                watch: None,
+                cache: None,
+                into_archives: false,
+                age_buckets: AgeBuckets::default(),
+                allow_delete: false,
+                palette: Palette::disabled(),
+                width: None,
+                size_format: SizeFormat::default(),
+            },
+        }
+    }
+
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.clone()
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    pub fn format(&self) -> &Format {
+        &self.format
+    }
+
+    pub fn tree_style(&self) -> &TreeStyle {
+        &self.tree_style
+    }
+
+    pub fn svg_layout(&self) -> &Layout {
+        &self.svg_layout
+    }
+
+    pub fn svg_coloring(&self) -> &Coloring {
+        &self.svg_coloring
+    }
+
+    pub fn svg_size(&self) -> (usize, usize) {
+        self.svg_size
+    }
+
+    // Without a depth only the children of the root are shown, except in the HTML report. That
+    // can be zoomed into, so it has everything in it unless asked not to.
+    pub fn depth_accepts(&self, level: usize) -> bool {
+        match self.depth {
+            Some(ref depth) => depth.accepts(level),
+            None if self.format == Format::Html => true,
+            None => Depth::Limited(1).accepts(level),
+        }
+    }
+
+    pub fn limit_reached(&self, shown_entries: usize) -> bool {
+        match self.limit {
+            Limit::Unlimited => false,
+            Limit::Limited(max) => max <= shown_entries
+        }
+    }
+
+    pub fn should_show_hidden(&self) -> bool {
+        self.show_all
+    }
+
+    pub fn should_show_percent(&self) -> bool {
+        self.show_percent
+    }
+
+    pub fn should_show_bar(&self) -> bool {
+        self.show_bar
+    }
+
+    pub fn should_rank_cumulative(&self) -> bool {
+        self.rank_cumulative
+    }
+
+    pub fn should_show_absolute_paths(&self) -> bool {
+        self.absolute_paths
+    }
+
+    pub fn should_group_per_child(&self) -> bool {
+        self.group_per_child
+    }
+
+    pub fn is_large_enough(&self, size: u64) -> bool {
+        size >= self.min_size
+    }
+
+    pub fn owner(&self) -> Option<u32> {
+        self.owner
+    }
+
+    // Ages are in seconds.
+    pub fn older_than(&self) -> Option<u64> {
+        self.older_than
+    }
+
+    pub fn newer_than(&self) -> Option<u64> {
+        self.newer_than
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn age_buckets(&self) -> &AgeBuckets {
+        &self.age_buckets
+    }
+
+    pub fn should_separate_with_nul(&self) -> bool {
+        self.nul_separated
+    }
+
+    pub fn cleanable_rules(&self) -> &[Rule] {
+        &self.cleanable_rules
+    }
+
+    pub fn snapshot_to_save(&self) -> Option<&PathBuf> {
+        self.save.as_ref()
+    }
+
+    pub fn snapshot_to_load(&self) -> Option<&PathBuf> {
+        self.load.as_ref()
+    }
+
+    pub fn snapshot_to_diff(&self) -> Option<&PathBuf> {
+        self.diff.as_ref()
+    }
+
+    pub fn watch_interval(&self) -> Option<Duration> {
+        self.watch
+    }
+
+    pub fn cache_file(&self) -> Option<&PathBuf> {
+        self.cache.as_ref()
+    }
+
+    pub fn should_look_into_archives(&self) -> bool {
+        self.into_archives
+    }
+
+    pub fn should_allow_delete(&self) -> bool {
+        self.allow_delete
+    }
+
+    // Output is not colored and has no width to fit in unless given, since it does not have to go
+    // to a terminal. The dutop command takes both from the terminal it prints to.
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    pub fn size_format(&self) -> &SizeFormat {
+        &self.size_format
+    }
+}
+
+// Setters are named after the accessors, without their "should", like:
+//
+//     let options = Options::builder().mode(Mode::Files).limit(Limit::Unlimited).build();
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    pub fn build(self) -> Options {
+        self.options
+    }
+
+    pub fn roots(mut self, roots: Vec<PathBuf>) -> OptionsBuilder {
+        self.options.roots = roots;
+        self
+    }
+
+    pub fn limit(mut self, limit: Limit) -> OptionsBuilder {
+        self.options.limit = limit;
+        self
+    }
+
+    pub fn depth(mut self, depth: Depth) -> OptionsBuilder {
+        self.options.depth = Some(depth);
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> OptionsBuilder {
+        self.options.mode = mode;
+        self
+    }
+
+    pub fn format(mut self, format: Format) -> OptionsBuilder {
+        self.options.format = format;
+        self
+    }
+
+    pub fn tree_style(mut self, tree_style: TreeStyle) -> OptionsBuilder {
+        self.options.tree_style = tree_style;
+        self
+    }
+
+    pub fn svg_layout(mut self, svg_layout: Layout) -> OptionsBuilder {
+        self.options.svg_layout = svg_layout;
+        self
+    }
+
+    pub fn svg_coloring(mut self, svg_coloring: Coloring) -> OptionsBuilder {
+        self.options.svg_coloring = svg_coloring;
+        self
+    }
+
+    pub fn svg_size(mut self, width: usize, height: usize) -> OptionsBuilder {
+        self.options.svg_size = (width, height);
+        self
+    }
+
+    pub fn show_hidden(mut self, show_hidden: bool) -> OptionsBuilder {
+        self.options.show_all = show_hidden;
+        self
+    }
+
+    pub fn show_percent(mut self, show_percent: bool) -> OptionsBuilder {
+        self.options.show_percent = show_percent;
+        self
+    }
+
+    pub fn show_bar(mut self, show_bar: bool) -> OptionsBuilder {
+        self.options.show_bar = show_bar;
+        self
+    }
+
+    pub fn rank_cumulative(mut self, rank_cumulative: bool) -> OptionsBuilder {
+        self.options.rank_cumulative = rank_cumulative;
+        self
+    }
+
+    pub fn show_absolute_paths(mut self, absolute_paths: bool) -> OptionsBuilder {
+        self.options.absolute_paths = absolute_paths;
+        self
+    }
+
+    pub fn group_per_child(mut self, group_per_child: bool) -> OptionsBuilder {
+        self.options.group_per_child = group_per_child;
+        self
+    }
+
+    pub fn min_size(mut self, min_size: u64) -> OptionsBuilder {
+        self.options.min_size = min_size;
+        self
+    }
+
+    pub fn owner(mut self, uid: u32) -> OptionsBuilder {
+        self.options.owner = Some(uid);
+        self
+    }
+
+    pub fn older_than(mut self, seconds: u64) -> OptionsBuilder {
+        self.options.older_than = Some(seconds);
+        self
+    }
+
+    pub fn newer_than(mut self, seconds: u64) -> OptionsBuilder {
+        self.options.newer_than = Some(seconds);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: Timestamp) -> OptionsBuilder {
+        self.options.timestamp = timestamp;
+        self
+    }
+
+    pub fn age_buckets(mut self, age_buckets: AgeBuckets) -> OptionsBuilder {
+        self.options.age_buckets = age_buckets;
+        self
+    }
+
+    pub fn separate_with_nul(mut self, nul_separated: bool) -> OptionsBuilder {
+        self.options.nul_separated = nul_separated;
+        self
+    }
+
+    // Replaces the built-in rules, so they have to be given as well to be kept.
+    pub fn cleanable_rules(mut self, cleanable_rules: Vec<Rule>) -> OptionsBuilder {
+        self.options.cleanable_rules = cleanable_rules;
+        self
+    }
+
+    pub fn snapshot_to_save(mut self, path: PathBuf) -> OptionsBuilder {
+        self.options.save = Some(path);
+        self
+    }
+
+    pub fn snapshot_to_load(mut self, path: PathBuf) -> OptionsBuilder {
+        self.options.load = Some(path);
+        self
+    }
+
+    pub fn snapshot_to_diff(mut self, path: PathBuf) -> OptionsBuilder {
+        self.options.diff = Some(path);
+        self
+    }
+
+    pub fn watch_interval(mut self, interval: Duration) -> OptionsBuilder {
+        self.options.watch = Some(interval);
+        self
+    }
+
+    pub fn cache_file(mut self, path: PathBuf) -> OptionsBuilder {
+        self.options.cache = Some(path);
+        self
+    }
+
+    pub fn look_into_archives(mut self, into_archives: bool) -> OptionsBuilder {
+        self.options.into_archives = into_archives;
+        self
+    }
+
+    pub fn allow_delete(mut self, allow_delete: bool) -> OptionsBuilder {
+        self.options.allow_delete = allow_delete;
+        self
+    }
+
+    pub fn palette(mut self, palette: Palette) -> OptionsBuilder {
+        self.options.palette = palette;
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> OptionsBuilder {
+        self.options.width = Some(width);
+        self
+    }
+
+    pub fn size_format(mut self, size_format: SizeFormat) -> OptionsBuilder {
+        self.options.size_format = size_format;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_starts_out_like_the_command_without_arguments() {
+        let options = Options::builder().build();
+
+        assert_eq!(options.roots(), vec![PathBuf::from(".")]);
+        assert_eq!(options.mode(), &Mode::Tree);
+        assert_eq!(options.format(), &Format::Text);
+        assert_eq!(options.should_show_hidden(), false);
+        assert_eq!(options.cleanable_rules(), &cleanable::default_rules()[..]);
+
+        assert_eq!(options.limit_reached(0), false);
+        assert_eq!(options.limit_reached(1), true);
+
+        assert_eq!(options.depth_accepts(0), true);
+        assert_eq!(options.depth_accepts(1), false);
+    }
+
+    #[test]
+    fn it_has_unlimited_depth_in_html_unless_given_a_depth() {
+        let options = Options::builder().format(Format::Html).build();
+        assert_eq!(options.depth_accepts(100), true);
+
+        let options = Options::builder().format(Format::Html).depth(Depth::Limited(1)).build();
+        assert_eq!(options.depth_accepts(1), false);
+    }
+
+    #[test]
+    fn it_sets_what_it_is_given() {
+        let options = Options::builder()
+            .limit(Limit::Unlimited)
+            .depth(Depth::Limited(3))
+            .mode(Mode::Files)
+            .min_size(2048)
+            .watch_interval(Duration::from_secs(60))
+            .build();
+
+        assert_eq!(options.limit_reached(1000), false);
+        assert_eq!(options.depth_accepts(2), true);
+        assert_eq!(options.depth_accepts(3), false);
+        assert_eq!(options.mode(), &Mode::Files);
+        assert_eq!(options.is_large_enough(2047), false);
+        assert_eq!(options.watch_interval(), Some(Duration::from_secs(60)));
+    }
+
+    // Depth
+
+    #[test]
+    fn it_parses_positive_depth_from_strings() {
+        assert_eq!("12".parse::<Depth>(), Ok(Depth::Limited(12)));
+    }
+
+    #[test]
+    fn it_parses_zero_depth_from_strings() {
+        assert_eq!("0".parse::<Depth>(), Ok(Depth::Unlimited));
+    }
+
+    #[test]
+    fn it_parses_named_alias_for_unlimited_depth_from_strings() {
+        assert_eq!("all".parse::<Depth>(), Ok(Depth::Unlimited));
+    }
+
+    #[test]
+    fn it_rejects_broken_depth_strings() {
+        assert_eq!(
+            "totally broken".parse::<Depth>(),
+            Err("Not a positive integer or \"all\"".to_string())
+        );
+    }
+
+    // Limit
+
+    #[test]
+    fn it_parses_positive_limit_from_strings() {
+        assert_eq!("12".parse::<Limit>(), Ok(Limit::Limited(12)));
+    }
+
+    #[test]
+    fn it_parses_zero_limit_from_strings() {
+        assert_eq!("0".parse::<Limit>(), Ok(Limit::Unlimited));
+    }
+
+    #[test]
+    fn it_parses_named_alias_for_unlimited_limit_from_strings() {
+        assert_eq!("all".parse::<Limit>(), Ok(Limit::Unlimited));
+    }
+
+    #[test]
+    fn it_rejects_broken_limit_strings() {
+        assert_eq!(
+            "totally broken".parse::<Limit>(),
+            Err("Not a positive integer or \"all\"".to_string())
+        );
+    }
+}
@@ -1,39 +1,124 @@
+use std::ffi::OsString;
 use std::fmt;
-use std::fs;
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 use std::slice::Iter;
 
 use modes::DisplayableEntry;
 use entry::Entry;
+use filesystem::{FileSystem, Metadata, RealFileSystem};
+use snapshot::{Decoder, Encoder};
 use utils;
-use utils::SizeDisplay;
+use utils::{SizeDisplay, SizeFormat};
+use walk::Walk;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Root {
     name: String,
+    // Like the names of entries, paths that are not valid UTF-8 are kept as they are.
+    os_name: Option<OsString>,
     entry: Entry,
 }
 
 impl Root {
     pub fn for_path(path: &Path) -> Result<Root, String> {
-        match fs::metadata(path) {
-            Ok(metadata) => Root::from_metadata(path, &metadata),
+        Root::for_path_in(&RealFileSystem, path)
+    }
+
+    pub fn for_path_in<F: FileSystem>(fs: &F, path: &Path) -> Result<Root, String> {
+        Root::scan_path(fs, path, false)
+    }
+
+    // Like Entry::scan_path.
+    pub fn scan_path<F: FileSystem>(fs: &F,
+                                    path: &Path,
+                                    into_archives: bool) -> Result<Root, String> {
+        match fs.metadata(path) {
+            Ok(metadata) => Root::from_metadata(fs, path, &metadata, into_archives),
             Err(error) => Err(utils::describe_io_error(error))
         }
     }
 
-    fn from_metadata(path: &Path, metadata: &fs::Metadata) -> Result<Root, String> {
-        Entry::from_metadata(path, &metadata).map(|entry| {
+    // Reuses what an earlier scan of the same path found where nothing changed, like
+    // Entry::rescan.
+    pub fn rescan<F: FileSystem>(fs: &F,
+                                 path: &Path,
+                                 previous: Root,
+                                 scanned_at: i64,
+                                 into_archives: bool) -> Result<Root, String> {
+        Entry::rescan(fs, path, previous.entry, scanned_at, into_archives).map(|entry| {
+            Root {
+                name: utils::full_name_from_path(path, !entry.is_file()),
+                os_name: utils::non_utf8_name(path.as_os_str()),
+                entry: entry,
+            }
+        })
+    }
+
+    fn from_metadata<F: FileSystem>(fs: &F,
+                                    path: &Path,
+                                    metadata: &Metadata,
+                                    into_archives: bool) -> Result<Root, String> {
+        Entry::from_metadata(fs, path, metadata, into_archives).map(|entry| {
             Root{
-                name: utils::full_name_from_path(path, metadata.is_dir()),
+                name: utils::full_name_from_path(path, !entry.is_file()),
+                os_name: utils::non_utf8_name(path.as_os_str()),
                 entry: entry,
             }
         })
     }
 
+    // The path that was scanned. It differs from the name that is shown when it is not valid
+    // UTF-8.
+    pub fn path(&self) -> PathBuf {
+        match self.os_name {
+            Some(ref name) => PathBuf::from(name),
+            None => PathBuf::from(&self.name),
+        }
+    }
+
     pub fn entry(&self) -> &Entry {
         &self.entry
     }
+
+    pub fn entry_mut(&mut self) -> &mut Entry {
+        &mut self.entry
+    }
+
+    // Like Entry::walk, with paths relative to the root.
+    pub fn walk(&self) -> Walk<'_> {
+        self.entry.walk()
+    }
+
+    pub fn encode<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        self.encode_as(&self.name, encoder)
+    }
+
+    // Like encode, with another name for the root.
+    pub fn encode_as<W: Write>(&self, name: &str, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_str(name)?;
+        self.entry.encode(encoder)
+    }
+
+    pub fn decode<R: Read>(decoder: &mut Decoder<R>) -> Result<Root, String> {
+        let name = decoder.read_string()?;
+        let entry = Entry::decode(decoder)?;
+        Ok(Root { name: name, os_name: None, entry: entry })
+    }
+
+    // Roots are kept even when nothing in them matches, since the user asked for them.
+    pub fn filtered<F>(self, keep: &F) -> Root where F: Fn(&Entry) -> bool {
+        let name = self.name;
+        let os_name = self.os_name;
+        let entry = self.entry;
+        let empty = entry.without_contents();
+
+        Root {
+            name: name,
+            os_name: os_name,
+            entry: entry.filtered(keep).unwrap_or(empty),
+        }
+    }
 }
 
 impl DisplayableEntry for Root {
@@ -47,7 +132,7 @@ impl DisplayableEntry for Root {
         &self.name
     }
 
-    fn children_iter(&self) -> Iter<Entry> {
+    fn children_iter(&self) -> Iter<'_, Entry> {
         self.entry.children_iter()
     }
 
@@ -55,6 +140,14 @@ impl DisplayableEntry for Root {
         self.entry.is_file()
     }
 
+    fn compressed_size(&self) -> Option<u64> {
+        self.entry.compressed_size()
+    }
+
+    fn uncompressed_size(&self) -> Option<u64> {
+        self.entry.uncompressed_size()
+    }
+
     fn is_hidden(&self) -> bool {
         // Roots are never hidden; we always want to show them since the user gave them to us
         // explicitly.
@@ -66,16 +159,33 @@ impl DisplayableEntry for Root {
 
 impl fmt::Display for Root {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {}", self.name(), self.entry.size().as_size_display())
+        let size = self.entry.size().as_size_display(&SizeFormat::default());
+        write!(f, "{} {}", self.name(), size)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use filesystem::MemoryFileSystem;
     use modes::DisplayableEntry;
     use std::path::Path;
 
+    #[test]
+    fn it_scans_a_file_system() {
+        let fs = MemoryFileSystem::new().file("home/user/notes", 20).file("home/user/todo", 5);
+        let root = Root::for_path_in(&fs, Path::new("home/user")).unwrap();
+
+        assert_eq!(root.name(), "home/user/");
+        assert_eq!(root.size(), 25);
+        assert_eq!(root.children_iter().map(|child| child.name().as_str()).collect::<Vec<_>>(),
+                   vec!["notes", "todo"]);
+
+        let file = Root::for_path_in(&fs, Path::new("home/user/todo")).unwrap();
+        assert_eq!(file.name(), "home/user/todo");
+        assert_eq!(Root::for_path_in(&fs, Path::new("home/other")).unwrap_err(), "File not found");
+    }
+
     #[test]
     fn it_can_be_constructed_with_a_path() {
         let pwd = Root::for_path(Path::new(".")).unwrap();
@@ -95,10 +205,11 @@ mod test {
 
     #[test]
     fn it_can_be_displayed() {
-        use utils::SizeDisplay;
+        use utils::{SizeDisplay, SizeFormat};
 
         let root = Root::for_path(Path::new("./LICENSE")).unwrap();
-        assert_eq!(format!("{}", root), format!("./LICENSE {}", root.size().as_size_display()));
+        let size = root.size().as_size_display(&SizeFormat::default());
+        assert_eq!(format!("{}", root), format!("./LICENSE {}", size));
     }
 
     #[test]
@@ -112,6 +223,15 @@ mod test {
         assert_eq!(dir_with_slash.name(), "./src/");
     }
 
+    #[test]
+    fn it_is_kept_when_nothing_matches() {
+        let root = Root::for_path(Path::new("./src")).unwrap().filtered(&|_: &Entry| false);
+
+        assert_eq!(root.name(), "./src/");
+        assert_eq!(root.size(), 0);
+        assert_eq!(root.children_iter().count(), 0);
+    }
+
     #[test]
     fn it_calculates_size_from_children() {
         let root = Root::for_path(Path::new(".")).unwrap();
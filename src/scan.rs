@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use cache::Cache;
+use filesystem::{FileSystem, RealFileSystem};
+use root::Root;
+
+// How roots are scanned. A scanner looks at the file system of the machine unless given another
+// one, and can be given a cache to keep every scan in, so scanning the same root again only looks
+// at what changed:
+//
+//     let mut scanner = Scanner::new().cache(Cache::new());
+//     let first = scanner.scan(Path::new("."))?;
+//     let second = scanner.scan(Path::new("."))?;
+//
+// Archives are only looked into when asked to, since every one of them has to be read.
+#[derive(Debug, Default)]
+pub struct Scanner<F: FileSystem = RealFileSystem> {
+    fs: F,
+    cache: Option<Cache>,
+    into_archives: bool,
+}
+
+impl Scanner {
+    pub fn new() -> Scanner {
+        Scanner::with_file_system(RealFileSystem)
+    }
+}
+
+impl<F: FileSystem> Scanner<F> {
+    pub fn with_file_system(fs: F) -> Scanner<F> {
+        Scanner { fs: fs, cache: None, into_archives: false }
+    }
+
+    pub fn cache(mut self, cache: Cache) -> Scanner<F> {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn into_archives(mut self, into_archives: bool) -> Scanner<F> {
+        self.into_archives = into_archives;
+        self
+    }
+
+    // Where the roots are scanned from.
+    pub fn file_system(&self) -> &F {
+        &self.fs
+    }
+
+    pub fn scan(&mut self, path: &Path) -> Result<Root, String> {
+        match self.cache {
+            Some(ref mut cache) => cache.scan(&self.fs, path, self.into_archives),
+            None => Root::scan_path(&self.fs, path, self.into_archives),
+        }
+    }
+
+    // Saves the cache so a later scanner can start from it. Scanners without a cache have nothing
+    // to save.
+    pub fn save_cache(&self, path: &Path) -> Result<(), String> {
+        match self.cache {
+            Some(ref cache) => cache.save(path),
+            None => Ok(()),
+        }
+    }
+}
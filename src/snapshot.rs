@@ -0,0 +1,259 @@
+use std::cmp;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use modes::DisplayableEntry;
+use root::Root;
+use utils;
+
+// Snapshots start with these bytes, followed by the version of the format. Files written by a
+// newer version are refused instead of misread.
+const MAGIC: &'static [u8] = b"DUTOPSNP";
+const VERSION: u8 = 3;
+
+// The roots of a scan, and when it was made.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub created: i64,
+    pub roots: Vec<Root>,
+}
+
+impl Snapshot {
+    // How long ago the snapshot was made, like "3 days".
+    pub fn age(&self) -> String {
+        utils::describe_duration(cmp::max(utils::now() - self.created, 0) as u64)
+    }
+}
+
+pub fn save(path: &Path, roots: &[Root]) -> Result<(), String> {
+    let file = File::create(path).map_err(utils::describe_io_error)?;
+    let mut encoder = Encoder::new(BufWriter::new(file));
+    write_snapshot(&mut encoder, roots).map_err(utils::describe_io_error)
+}
+
+fn write_snapshot<W: Write>(encoder: &mut Encoder<W>, roots: &[Root]) -> io::Result<()> {
+    encoder.write_bytes(MAGIC)?;
+    encoder.write_bytes(&[VERSION])?;
+    encoder.write_i64(utils::now())?;
+    encoder.write_u64(roots.len() as u64)?;
+    for root in roots {
+        // Roots are saved with their full path, so they are the same when loaded somewhere else.
+        root.encode_as(&utils::canonical_name(root.name()), encoder)?;
+    }
+    encoder.flush()
+}
+
+pub fn load(path: &Path) -> Result<Snapshot, String> {
+    let file = File::open(path).map_err(utils::describe_io_error)?;
+    let mut decoder = Decoder::new(BufReader::new(file));
+
+    let mut magic = vec![0u8; MAGIC.len()];
+    decoder.read_bytes(&mut magic)?;
+    if magic != MAGIC {
+        return Err("Not a snapshot".to_string());
+    }
+
+    let mut version = [0u8];
+    decoder.read_bytes(&mut version)?;
+    if version[0] != VERSION {
+        return Err(format!("Unsupported snapshot version {}", version[0]));
+    }
+
+    let created = decoder.read_i64()?;
+    let count = decoder.read_u64()?;
+    let roots = (0..count).map(|_| Root::decode(&mut decoder)).collect::<Result<_, _>>()?;
+
+    Ok(Snapshot { created: created, roots: roots })
+}
+
+// Numbers are written as variable-length integers (LEB128), so small numbers take up a single byte.
+// Signed numbers are zigzag encoded first, and strings are prefixed by their length.
+pub struct Encoder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(writer: W) -> Encoder<W> {
+        Encoder { writer: writer }
+    }
+
+    pub fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        let mut value = value;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                return self.writer.write_all(&[byte]);
+            }
+            self.writer.write_all(&[byte | 0x80])?;
+        }
+    }
+
+    pub fn write_i64(&mut self, value: i64) -> io::Result<()> {
+        self.write_u64(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> io::Result<()> {
+        self.writer.write_all(&[value as u8])
+    }
+
+    pub fn write_str(&mut self, value: &str) -> io::Result<()> {
+        self.write_u64(value.len() as u64)?;
+        self.writer.write_all(value.as_bytes())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub struct Decoder<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Decoder<R> {
+        Decoder { reader: reader }
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, String> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0u8];
+            self.read_bytes(&mut byte)?;
+            value |= ((byte[0] & 0x7f) as u64) << shift;
+            if byte[0] & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(corrupt())
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, String> {
+        let value = self.read_u64()?;
+        Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        let value = self.read_u64()?;
+        if value > u32::max_value() as u64 {
+            Err(corrupt())
+        } else {
+            Ok(value as u32)
+        }
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, String> {
+        let mut byte = [0u8];
+        self.read_bytes(&mut byte)?;
+        match byte[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt()),
+        }
+    }
+
+    pub fn read_string(&mut self) -> Result<String, String> {
+        let length = self.read_u64()?;
+        let mut bytes = Vec::new();
+        self.reader.by_ref().take(length).read_to_end(&mut bytes)
+            .map_err(utils::describe_io_error)?;
+
+        if bytes.len() as u64 != length {
+            return Err(corrupt());
+        }
+        String::from_utf8(bytes).map_err(|_| corrupt())
+    }
+
+    fn read_bytes(&mut self, bytes: &mut [u8]) -> Result<(), String> {
+        self.reader.read_exact(bytes).map_err(|error| {
+            if error.kind() == io::ErrorKind::UnexpectedEof {
+                corrupt()
+            } else {
+                utils::describe_io_error(error)
+            }
+        })
+    }
+}
+
+fn corrupt() -> String {
+    "The snapshot is damaged".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Cursor;
+    use utils::tests::TempDir;
+
+    fn encoded<F>(write: F) -> Vec<u8> where F: Fn(&mut Encoder<&mut Vec<u8>>) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        write(&mut Encoder::new(&mut bytes)).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn it_encodes_numbers_compactly() {
+        assert_eq!(encoded(|encoder| encoder.write_u64(0)), vec![0]);
+        assert_eq!(encoded(|encoder| encoder.write_u64(127)), vec![127]);
+        assert_eq!(encoded(|encoder| encoder.write_u64(300)), vec![0xac, 0x02]);
+        assert_eq!(encoded(|encoder| encoder.write_i64(-1)), vec![1]);
+        assert_eq!(encoded(|encoder| encoder.write_i64(1)), vec![2]);
+    }
+
+    #[test]
+    fn it_decodes_what_it_encodes() {
+        let bytes = encoded(|encoder| {
+            encoder.write_u64(u64::max_value())?;
+            encoder.write_i64(i64::min_value())?;
+            encoder.write_i64(1_500_000_000)?;
+            encoder.write_bool(true)?;
+            encoder.write_str("naïve/")
+        });
+
+        let mut decoder = Decoder::new(Cursor::new(bytes));
+        assert_eq!(decoder.read_u64(), Ok(u64::max_value()));
+        assert_eq!(decoder.read_i64(), Ok(i64::min_value()));
+        assert_eq!(decoder.read_i64(), Ok(1_500_000_000));
+        assert_eq!(decoder.read_bool(), Ok(true));
+        assert_eq!(decoder.read_string(), Ok("naïve/".to_string()));
+        assert_eq!(decoder.read_u64(), Err(corrupt()));
+    }
+
+    #[test]
+    fn it_saves_and_loads_roots() {
+        let directory = TempDir::new("snapshot");
+        let path = directory.join("snapshot");
+        let roots = vec![
+            Root::for_path(Path::new("./src")).unwrap(),
+            Root::for_path(Path::new("./LICENSE")).unwrap(),
+        ];
+
+        save(&path, &roots).unwrap();
+        let snapshot = load(&path).unwrap();
+
+        assert!((snapshot.created - utils::now()).abs() < 60);
+        assert_eq!(snapshot.roots.len(), 2);
+        assert_eq!(format!("{:?}", snapshot.roots[0].entry()), format!("{:?}", roots[0].entry()));
+        assert_eq!(snapshot.roots[0].size(), roots[0].size());
+
+        let current = env::current_dir().unwrap();
+        let names: Vec<&String> = snapshot.roots.iter().map(|root| root.name()).collect();
+        assert_eq!(names, vec![
+            &format!("{}/src/", current.to_string_lossy()),
+            &format!("{}/LICENSE", current.to_string_lossy()),
+        ]);
+    }
+
+    #[test]
+    fn it_refuses_files_that_are_not_snapshots() {
+        assert_eq!(load(Path::new("./LICENSE")).unwrap_err(), "Not a snapshot");
+        assert_eq!(load(Path::new("./does-not-exist")).unwrap_err(), "File not found");
+    }
+}
@@ -0,0 +1,385 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::io;
+use std::str::FromStr;
+
+use options::Options;
+use layout::{self, Arc, Rect};
+use modes::{visible_children, DisplayableEntry};
+use root::Root;
+use utils;
+use utils::SizeDisplay;
+
+const LABEL_HEIGHT: f64 = 14.0;
+const CHARACTER_WIDTH: f64 = 6.5;
+const PADDING: f64 = 2.0;
+
+const DEPTH_PALETTE: [&'static str; 6] = [
+    "#8da0cb", "#66c2a5", "#fc8d62", "#e78ac3", "#a6d854", "#ffd92f",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Layout {
+    Treemap,
+    Sunburst,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "treemap" => Ok(Layout::Treemap),
+            "sunburst" => Ok(Layout::Sunburst),
+            _ => Err(format!("Unknown layout \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Coloring {
+    Depth,
+    Extension,
+}
+
+impl FromStr for Coloring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "depth" => Ok(Coloring::Depth),
+            "extension" => Ok(Coloring::Extension),
+            _ => Err(format!("Unknown coloring \"{}\"", s)),
+        }
+    }
+}
+
+pub fn write_svg<W: io::Write>(root: &Root, options: &Options, out: &mut W) -> io::Result<()> {
+    writeln!(out, "{}", render(root, options))
+}
+
+fn render(root: &Root, options: &Options) -> String {
+    let (width, height) = options.svg_size();
+    let mut out = String::new();
+
+    writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+                   viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"11\">",
+             width, height).unwrap();
+
+    match options.svg_layout() {
+        &Layout::Treemap => {
+            let bounds = Rect::new(0.0, 0.0, width as f64, height as f64);
+            draw_treemap(root, bounds, 0, options, &mut out);
+        },
+        &Layout::Sunburst => {
+            // Images too small to fit the padding around the circle are left empty.
+            let radius = (width.min(height) as f64) / 2.0 - PADDING;
+            if radius > 0.0 {
+                let rings = levels(root, options, 0) as f64;
+                let center = (width as f64 / 2.0, height as f64 / 2.0);
+                draw_sunburst(root, Arc::full(), 0, center, radius / rings, options, &mut out);
+            }
+        },
+    }
+
+    out.push_str("</svg>");
+    out
+}
+
+fn draw_treemap<T: DisplayableEntry>(entry: &T,
+                                     bounds: Rect,
+                                     level: usize,
+                                     options: &Options,
+                                     out: &mut String) {
+    writeln!(out, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" \
+                   stroke=\"#fff\"><title>{}</title></rect>",
+             bounds.x, bounds.y, bounds.w, bounds.h,
+             fill(entry, level, options.svg_coloring()), title(entry, options)).unwrap();
+
+    let label = label(entry, options);
+    let has_label = fits(&label, bounds.w - 2.0 * PADDING) && bounds.h >= LABEL_HEIGHT;
+    if has_label {
+        writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                 bounds.x + PADDING + 1.0, bounds.y + LABEL_HEIGHT - 3.0,
+                 utils::escape_markup(&label)).unwrap();
+    }
+
+    if entry.is_file() || !options.depth_accepts(level) {
+        return;
+    }
+
+    let inner = if has_label {
+        bounds.inset(LABEL_HEIGHT, PADDING, PADDING, PADDING)
+    } else {
+        bounds.inset(PADDING, PADDING, PADDING, PADDING)
+    };
+    if inner.w < 2.0 * PADDING || inner.h < 2.0 * PADDING {
+        return;
+    }
+
+    let children = visible_children(entry, options);
+    let sizes: Vec<u64> = children.iter().map(|child| child.size()).collect();
+    for (child, rect) in children.iter().zip(layout::squarify(&sizes, inner)) {
+        if rect.area() >= 1.0 {
+            draw_treemap(*child, rect, level + 1, options, out);
+        }
+    }
+}
+
+fn draw_sunburst<T: DisplayableEntry>(entry: &T,
+                                      arc: Arc,
+                                      level: usize,
+                                      center: (f64, f64),
+                                      ring: f64,
+                                      options: &Options,
+                                      out: &mut String) {
+    let inner = level as f64 * ring;
+    let outer = inner + ring;
+    let fill = fill(entry, level, options.svg_coloring());
+
+    if level == 0 {
+        writeln!(out, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\" \
+                       stroke=\"#fff\"><title>{}</title></circle>",
+                 center.0, center.1, outer, fill, title(entry, options)).unwrap();
+    } else {
+        writeln!(out, "<path d=\"{}\" fill=\"{}\" fill-rule=\"evenodd\" stroke=\"#fff\">\
+                       <title>{}</title></path>",
+                 sector_path(center, inner, outer, arc), fill, title(entry, options)).unwrap();
+    }
+
+    let label = label(entry, options);
+    let middle = (inner + outer) / 2.0;
+    let (room, x, y) = if level == 0 {
+        (2.0 * outer, center.0, center.1)
+    } else {
+        let angle = arc.start + arc.sweep() / 2.0;
+        (arc.sweep() * middle, center.0 + middle * angle.sin(), center.1 - middle * angle.cos())
+    };
+    if fits(&label, room - 2.0 * PADDING) && ring >= LABEL_HEIGHT {
+        writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" \
+                       dominant-baseline=\"middle\">{}</text>",
+                 x, y, utils::escape_markup(&label)).unwrap();
+    }
+
+    if entry.is_file() || !options.depth_accepts(level) {
+        return;
+    }
+
+    let children = visible_children(entry, options);
+    let sizes: Vec<u64> = children.iter().map(|child| child.size()).collect();
+    for (child, child_arc) in children.iter().zip(layout::split_arc(&sizes, arc)) {
+        // Skip slivers that would be less than half a pixel wide at the outer edge.
+        if child_arc.sweep() * (outer + ring) >= 0.5 {
+            draw_sunburst(*child, child_arc, level + 1, center, ring, options, out);
+        }
+    }
+}
+
+// Angles start at 12 o'clock and go clockwise.
+fn sector_path(center: (f64, f64), inner: f64, outer: f64, arc: Arc) -> String {
+    let point = |radius: f64, angle: f64| {
+        (center.0 + radius * angle.sin(), center.1 - radius * angle.cos())
+    };
+
+    if arc.sweep() >= 2.0 * PI - 1e-9 {
+        // A single arc cannot start and end in the same point, so full rings are drawn as two
+        // halves. The hole is cut out using the evenodd fill rule.
+        let (top_x, top_y) = point(outer, 0.0);
+        let (bottom_x, bottom_y) = point(outer, PI);
+        let (inner_top_x, inner_top_y) = point(inner, 0.0);
+        let (inner_bottom_x, inner_bottom_y) = point(inner, PI);
+        return format!(
+            "M{:.2},{:.2} A{4:.2},{4:.2} 0 1 1 {2:.2},{3:.2} \
+             A{4:.2},{4:.2} 0 1 1 {0:.2},{1:.2} Z \
+             M{5:.2},{6:.2} A{9:.2},{9:.2} 0 1 1 {7:.2},{8:.2} \
+             A{9:.2},{9:.2} 0 1 1 {5:.2},{6:.2} Z",
+            top_x, top_y, bottom_x, bottom_y, outer,
+            inner_top_x, inner_top_y, inner_bottom_x, inner_bottom_y, inner
+        );
+    }
+
+    let large_arc = if arc.sweep() > PI { 1 } else { 0 };
+    let (x1, y1) = point(outer, arc.start);
+    let (x2, y2) = point(outer, arc.end);
+    let (x3, y3) = point(inner, arc.end);
+    let (x4, y4) = point(inner, arc.start);
+
+    format!(
+        "M{:.2},{:.2} A{:.2},{:.2} 0 {} 1 {:.2},{:.2} \
+         L{:.2},{:.2} A{:.2},{:.2} 0 {} 0 {:.2},{:.2} Z",
+        x1, y1, outer, outer, large_arc, x2, y2,
+        x3, y3, inner, inner, large_arc, x4, y4
+    )
+}
+
+// The number of rings needed to show the entry, including the entry itself.
+fn levels<T: DisplayableEntry>(entry: &T, options: &Options, level: usize) -> usize {
+    if entry.is_file() || !options.depth_accepts(level) {
+        return 1;
+    }
+
+    1 + visible_children(entry, options)
+        .iter()
+        .map(|child| levels(*child, options, level + 1))
+        .max()
+        .unwrap_or(0)
+}
+
+fn fill<T: DisplayableEntry>(entry: &T, level: usize, coloring: &Coloring) -> String {
+    match coloring {
+        &Coloring::Depth => DEPTH_PALETTE[level % DEPTH_PALETTE.len()].to_string(),
+        &Coloring::Extension => {
+            if !entry.is_file() {
+                // Directories stay in the background with a gray that gets lighter further down.
+                let lightness = (70 + level * 5).min(92);
+                return format!("hsl(0, 0%, {}%)", lightness);
+            }
+
+            match utils::file_extension(entry.name()) {
+                Some(extension) => format!("hsl({}, 55%, 65%)", hue_for(&extension)),
+                None => "hsl(0, 0%, 60%)".to_string(),
+            }
+        },
+    }
+}
+
+// Gives every extension a stable hue by hashing its name (FNV-1a).
+fn hue_for(extension: &str) -> u32 {
+    let hash = extension.bytes().fold(2166136261u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(16777619)
+    });
+    hash % 360
+}
+
+fn label<T: DisplayableEntry>(entry: &T, options: &Options) -> String {
+    format!("{} {}", entry.name(), entry.size().as_size_display(options.size_format()))
+}
+
+fn title<T: DisplayableEntry>(entry: &T, options: &Options) -> String {
+    utils::escape_markup(&label(entry, options))
+}
+
+fn fits(label: &str, room: f64) -> bool {
+    label.chars().count() as f64 * CHARACTER_WIDTH <= room
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filesystem::MemoryFileSystem;
+    use formats::Format;
+    use layout::Arc;
+    use options::Limit;
+    use root::Root;
+    use std::f64::consts::PI;
+    use std::path::Path;
+
+    #[test]
+    fn it_parses_layouts_and_colorings() {
+        assert_eq!("treemap".parse::<Layout>(), Ok(Layout::Treemap));
+        assert_eq!("sunburst".parse::<Layout>(), Ok(Layout::Sunburst));
+        assert_eq!("depth".parse::<Coloring>(), Ok(Coloring::Depth));
+        assert_eq!("extension".parse::<Coloring>(), Ok(Coloring::Extension));
+        assert!("pie".parse::<Layout>().is_err());
+    }
+
+    #[test]
+    fn it_renders_a_treemap_of_the_given_size() {
+        let options = Options::builder().format(Format::Svg).svg_size(300, 200).build();
+        let root = Root::for_path(Path::new("./LICENSE")).unwrap();
+        let svg = render(&root, &options);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"300\" \
+                                 height=\"200\""));
+        assert!(svg.contains("<rect x=\"0.0\" y=\"0.0\" width=\"300.0\" height=\"200.0\""));
+        assert!(svg.contains(">./LICENSE "));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn it_renders_a_sunburst_with_the_root_in_the_center() {
+        let options = Options::builder()
+            .format(Format::Svg)
+            .svg_layout(Layout::Sunburst)
+            .svg_size(300, 200)
+            .build();
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let svg = render(&root, &options);
+
+        assert!(svg.contains("<circle cx=\"150.0\" cy=\"100.0\" r=\"49.0\""));
+        assert!(svg.contains("<path d=\"M"));
+    }
+
+    #[test]
+    fn it_renders_images_of_the_smallest_size() {
+        let root = Root::for_path(Path::new("./src")).unwrap();
+
+        let options = Options::builder().format(Format::Svg).svg_size(1, 1).build();
+        let svg = render(&root, &options);
+        assert!(svg.contains("<rect x=\"0.0\" y=\"0.0\" width=\"1.0\" height=\"1.0\""));
+
+        let options = Options::builder()
+            .format(Format::Svg)
+            .svg_layout(Layout::Sunburst)
+            .svg_size(1, 1)
+            .build();
+        let svg = render(&root, &options);
+        assert!(!svg.contains("<circle"));
+        assert!(!svg.contains("<path"));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn it_draws_the_children_that_the_limit_and_minimum_size_allow() {
+        let fs = MemoryFileSystem::new()
+            .file("project/large", 6000)
+            .file("project/medium", 3000)
+            .file("project/small", 100);
+        let root = Root::for_path_in(&fs, Path::new("project")).unwrap();
+        let draws = |options: Options| render(&root, &options).matches("<rect ").count();
+
+        let options = Options::builder().format(Format::Svg);
+        assert_eq!(draws(options.limit(Limit::Unlimited).build()), 4);
+
+        let options = Options::builder().format(Format::Svg);
+        assert_eq!(draws(options.limit(Limit::Limited(2)).build()), 3);
+
+        let options = Options::builder().format(Format::Svg);
+        assert_eq!(draws(options.limit(Limit::Unlimited).min_size(1000).build()), 3);
+    }
+
+    #[test]
+    fn it_only_labels_boxes_that_fit_the_label() {
+        assert!(fits("abc", 3.0 * CHARACTER_WIDTH));
+        assert!(!fits("abcd", 3.0 * CHARACTER_WIDTH));
+    }
+
+    #[test]
+    fn it_draws_full_rings_in_two_halves() {
+        let path = sector_path((10.0, 10.0), 2.0, 4.0, Arc::full());
+        assert_eq!(path.matches('A').count(), 4);
+
+        let half = sector_path((10.0, 10.0), 2.0, 4.0, Arc { start: 0.0, end: PI });
+        assert_eq!(half, "M10.00,6.00 A4.00,4.00 0 0 1 10.00,14.00 L10.00,12.00 \
+                          A2.00,2.00 0 0 0 10.00,8.00 Z");
+    }
+
+    #[test]
+    fn it_colors_files_by_extension() {
+        let root = Root::for_path(Path::new("./src/main.rs")).unwrap();
+        let same = Root::for_path(Path::new("./src/utils.rs")).unwrap();
+        let license = Root::for_path(Path::new("./LICENSE")).unwrap();
+
+        let rust = fill(&root, 0, &Coloring::Extension);
+        assert_eq!(rust, fill(&same, 3, &Coloring::Extension));
+        assert_eq!(rust, format!("hsl({}, 55%, 65%)", hue_for("rs")));
+        assert_eq!(fill(&license, 0, &Coloring::Extension), "hsl(0, 0%, 60%)");
+    }
+
+    #[test]
+    fn it_colors_by_depth() {
+        let root = Root::for_path(Path::new("./LICENSE")).unwrap();
+        assert_eq!(fill(&root, 0, &Coloring::Depth), DEPTH_PALETTE[0]);
+        assert_eq!(fill(&root, DEPTH_PALETTE.len() + 1, &Coloring::Depth), DEPTH_PALETTE[1]);
+    }
+}
@@ -0,0 +1,37 @@
+use std::env;
+use std::mem;
+
+use libc;
+
+// Width of the terminal that standard output is connected to. $COLUMNS takes precedence so the
+// width can be overridden, and so it still works when the output is piped somewhere.
+pub fn width() -> Option<usize> {
+    from_env("COLUMNS").or_else(|| window_size().map(|size| size.ws_col as usize))
+}
+
+// Height of the terminal, in the same manner as the width but using $LINES.
+pub fn height() -> Option<usize> {
+    from_env("LINES").or_else(|| window_size().map(|size| size.ws_row as usize))
+}
+
+pub fn is_interactive() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 && libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+fn from_env(name: &str) -> Option<usize> {
+    env::var(name).ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .and_then(|value| if value > 0 { Some(value) } else { None })
+}
+
+fn window_size() -> Option<libc::winsize> {
+    unsafe {
+        let mut size: libc::winsize = mem::zeroed();
+        let found = libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0;
+        if found && size.ws_col > 0 && size.ws_row > 0 {
+            Some(size)
+        } else {
+            None
+        }
+    }
+}
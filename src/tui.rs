@@ -0,0 +1,872 @@
+use std::cmp;
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use libc;
+
+use options::Options;
+use entry::Entry;
+use filesystem::FileSystem;
+use filters::Filter;
+use modes::{self, DisplayableEntry};
+use root::Root;
+use terminal;
+use utils::SizeDisplay;
+
+const BAR_WIDTH: usize = 20;
+const SIZE_WIDTH: usize = 11;
+// How long to wait for the rest of an escape sequence that was cut off, in milliseconds.
+const ESCAPE_WAIT: i32 = 50;
+const HELP: &'static str =
+    "↑↓ move  ←→ collapse/expand  enter toggle  s sort  r rescan  d delete  q quit";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum SortOrder {
+    SizeDescending,
+    SizeAscending,
+    Name,
+}
+
+impl SortOrder {
+    fn next(&self) -> SortOrder {
+        match *self {
+            SortOrder::SizeDescending => SortOrder::SizeAscending,
+            SortOrder::SizeAscending => SortOrder::Name,
+            SortOrder::Name => SortOrder::SizeDescending,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            SortOrder::SizeDescending => "largest first",
+            SortOrder::SizeAscending => "smallest first",
+            SortOrder::Name => "name",
+        }
+    }
+
+    // Entries are stored largest first, so that order is kept as-is. The sorts are stable, so
+    // entries of the same size stay in a predictable order.
+    fn sort(&self, children: &mut Vec<(usize, &Entry)>) {
+        match *self {
+            SortOrder::SizeDescending => {},
+            SortOrder::SizeAscending => children.sort_by_key(|&(_, child)| child.size()),
+            SortOrder::Name => children.sort_by_key(|&(_, child)| child.name().to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Interrupt,
+    Char(char),
+    Unknown,
+}
+
+// Everything that is read in one go is parsed, since that is how terminals send escape sequences.
+// A lone escape is the escape key itself, unless is_cut_off says to wait for the rest first.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let rest = &bytes[index..];
+        let (key, length) = match rest {
+            [0x1b, b'[', b'5', b'~', ..] => (Key::PageUp, 4),
+            [0x1b, b'[', b'6', b'~', ..] => (Key::PageDown, 4),
+            [0x1b, b'[', code, ..] | [0x1b, b'O', code, ..] => {
+                let key = match *code {
+                    b'A' => Key::Up,
+                    b'B' => Key::Down,
+                    b'C' => Key::Right,
+                    b'D' => Key::Left,
+                    b'H' => Key::Home,
+                    b'F' => Key::End,
+                    _ => Key::Unknown,
+                };
+                (key, 3)
+            },
+            [0x1b, ..] => (Key::Escape, 1),
+            [0x03, ..] => (Key::Interrupt, 1),
+            [b'\r', ..] | [b'\n', ..] => (Key::Enter, 1),
+            [byte, ..] if byte.is_ascii() && !byte.is_ascii_control() => {
+                (Key::Char(*byte as char), 1)
+            },
+            _ => (Key::Unknown, 1),
+        };
+
+        keys.push(key);
+        index += length;
+    }
+
+    keys
+}
+
+// Whether the bytes end in the beginning of an escape sequence, which happens when a sequence is
+// split across reads.
+fn is_cut_off(bytes: &[u8]) -> bool {
+    let start = bytes.len().saturating_sub(3);
+    match bytes[start..].iter().rposition(|&byte| byte == 0x1b) {
+        Some(position) => matches!(
+            &bytes[start + position..],
+            [0x1b] | [0x1b, b'['] | [0x1b, b'O'] | [0x1b, b'[', b'5'] | [0x1b, b'[', b'6']
+        ),
+        None => false,
+    }
+}
+
+// A visible line in the browser. Entries are found again through the indices of the children on
+// the way down from the root, while the path is used to remember what is expanded and selected
+// since it stays the same when the tree is rescanned or sorted. The path is made from the names
+// on disk, so it can differ from the names that are shown when they are not valid UTF-8.
+#[derive(Debug)]
+struct Line {
+    indices: Vec<usize>,
+    path: PathBuf,
+    depth: usize,
+    name: String,
+    size: u64,
+    parent_size: u64,
+    is_file: bool,
+    is_expanded: bool,
+    // Archives are deleted like files, and what is in them cannot be deleted or rescanned.
+    is_archive: bool,
+    is_in_archive: bool,
+}
+
+// Rescans are made the same way as the first scan, in the same file system and restricted by the
+// same filter.
+struct Browser<'a, F: FileSystem + 'a> {
+    fs: &'a F,
+    root: Root,
+    root_path: PathBuf,
+    options: &'a Options,
+    filter: Filter,
+    into_archives: bool,
+    expanded: HashSet<PathBuf>,
+    order: SortOrder,
+    lines: Vec<Line>,
+    selected: usize,
+    scroll: usize,
+    pending_delete: Option<usize>,
+    message: Option<String>,
+}
+
+impl<'a, F: FileSystem> Browser<'a, F> {
+    fn new(fs: &'a F,
+           root: Root,
+           options: &'a Options,
+           filter: Filter,
+           into_archives: bool) -> Browser<'a, F> {
+        let root_path = root.path();
+        let mut expanded = HashSet::new();
+        expanded.insert(root_path.clone());
+
+        let mut browser = Browser {
+            fs: fs,
+            root: root,
+            root_path: root_path,
+            options: options,
+            filter: filter,
+            into_archives: into_archives,
+            expanded: expanded,
+            order: SortOrder::SizeDescending,
+            lines: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            pending_delete: None,
+            message: None,
+        };
+        browser.refresh();
+        browser
+    }
+
+    fn refresh(&mut self) {
+        let mut lines = Vec::new();
+        let size = self.root.size();
+        lines.push(Line {
+            indices: Vec::new(),
+            path: self.root_path.clone(),
+            depth: 0,
+            name: self.root.name().clone(),
+            size: size,
+            parent_size: size,
+            is_file: self.root.is_file(),
+            is_expanded: self.expanded.contains(&self.root_path),
+            is_archive: self.root.entry().is_archive(),
+            is_in_archive: false,
+        });
+        if lines[0].is_expanded {
+            self.add_children(self.root.entry(), &[], &self.root_path, 1, &mut lines);
+        }
+
+        self.lines = lines;
+        self.selected = cmp::min(self.selected, self.lines.len() - 1);
+    }
+
+    fn add_children(&self,
+                    entry: &Entry,
+                    indices: &[usize],
+                    path: &Path,
+                    depth: usize,
+                    lines: &mut Vec<Line>) {
+        let mut children: Vec<(usize, &Entry)> = entry.children_iter()
+            .enumerate()
+            .filter(|&(_, child)| self.options.should_show_hidden() || !child.is_hidden())
+            .collect();
+        self.order.sort(&mut children);
+
+        for (index, child) in children {
+            let mut child_indices = indices.to_vec();
+            child_indices.push(index);
+            let child_path = path.join(child.file_name());
+            let is_expanded = !child.is_file() && self.expanded.contains(&child_path);
+
+            lines.push(Line {
+                indices: child_indices.clone(),
+                path: child_path.clone(),
+                depth: depth,
+                name: child.name().clone(),
+                size: child.size(),
+                parent_size: entry.size(),
+                is_file: child.is_file(),
+                is_expanded: is_expanded,
+                is_archive: child.is_archive(),
+                is_in_archive: child.is_in_archive(),
+            });
+
+            if is_expanded {
+                self.add_children(child, &child_indices, &child_path, depth + 1, lines);
+            }
+        }
+    }
+
+    fn select_path(&mut self, path: &Path) {
+        if let Some(index) = self.lines.iter().position(|line| line.path == path) {
+            self.selected = index;
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = cmp::min(index, self.lines.len() - 1);
+    }
+
+    // Returns false when the browser should be closed.
+    fn handle(&mut self, key: Key, page_height: usize) -> bool {
+        if let Some(index) = self.pending_delete.take() {
+            self.message = Some(if key == Key::Char('y') || key == Key::Char('Y') {
+                self.delete(index)
+            } else {
+                "Cancelled".to_string()
+            });
+            return true;
+        }
+
+        self.message = None;
+
+        match key {
+            Key::Char('q') | Key::Escape | Key::Interrupt => return false,
+            Key::Up | Key::Char('k') => {
+                let selected = self.selected;
+                self.select(selected.saturating_sub(1));
+            },
+            Key::Down | Key::Char('j') => {
+                let selected = self.selected;
+                self.select(selected + 1);
+            },
+            Key::PageUp => {
+                let selected = self.selected;
+                self.select(selected.saturating_sub(page_height));
+            },
+            Key::PageDown => {
+                let selected = self.selected;
+                self.select(selected + page_height);
+            },
+            Key::Home => self.select(0),
+            Key::End => {
+                let last = self.lines.len() - 1;
+                self.select(last);
+            },
+            Key::Left | Key::Char('h') => self.collapse(),
+            Key::Right | Key::Char('l') => self.expand(),
+            Key::Enter | Key::Char(' ') => {
+                if self.lines[self.selected].is_expanded {
+                    self.collapse();
+                } else {
+                    self.expand();
+                }
+            },
+            Key::Char('s') => {
+                let path = self.lines[self.selected].path.clone();
+                self.order = self.order.next();
+                self.refresh();
+                self.select_path(&path);
+                self.message = Some(format!("Sorted by {}", self.order.describe()));
+            },
+            Key::Char('r') => {
+                let index = self.selected;
+                self.message = Some(if self.lines[index].is_in_archive {
+                    "What is in an archive can only be rescanned with the archive".to_string()
+                } else {
+                    self.rescan(index)
+                });
+            },
+            Key::Char('d') => self.confirm_delete(),
+            _ => {},
+        }
+
+        true
+    }
+
+    // Collapsing something that is not expanded moves to its parent instead.
+    fn collapse(&mut self) {
+        let path = self.lines[self.selected].path.clone();
+        if self.lines[self.selected].is_expanded {
+            self.expanded.remove(&path);
+            self.refresh();
+        } else if self.lines[self.selected].depth > 0 {
+            let depth = self.lines[self.selected].depth;
+            let parent = self.lines[..self.selected].iter().rposition(|line| line.depth < depth);
+            if let Some(parent) = parent {
+                self.selected = parent;
+            }
+        }
+    }
+
+    // Expanding something that is already expanded moves to its first child instead.
+    fn expand(&mut self) {
+        let line = &self.lines[self.selected];
+        if line.is_file {
+            return;
+        }
+
+        if line.is_expanded {
+            let selected = self.selected;
+            if self.lines.get(selected + 1).map(|next| next.depth > line.depth) == Some(true) {
+                self.selected += 1;
+            }
+        } else {
+            let path = line.path.clone();
+            self.expanded.insert(path);
+            self.refresh();
+        }
+    }
+
+    fn rescan(&mut self, index: usize) -> String {
+        let indices = self.lines[index].indices.clone();
+        let path = self.lines[index].path.clone();
+
+        let fs = self.fs;
+        let into_archives = self.into_archives;
+        let result = match indices.split_last() {
+            None => Root::scan_path(fs, &self.root_path, into_archives).map(|root| {
+                self.root = self.filter.apply(root);
+            }),
+            Some((&last, parents)) => Entry::scan_path(fs, &path, into_archives).map(|entry| {
+                let entry = self.filter.apply_to_entry(entry);
+                if let Some(parent) = entry_at(self.root.entry_mut(), parents) {
+                    match entry {
+                        Some(entry) => parent.replace_child(last, entry),
+                        None => {
+                            parent.remove_child(last);
+                        },
+                    }
+                }
+            }),
+        };
+
+        self.refresh();
+        self.select_path(&path);
+
+        match result {
+            Ok(()) => format!("Rescanned {}", path.to_string_lossy()),
+            Err(message) => format!("Could not rescan {}: {}", path.to_string_lossy(), message),
+        }
+    }
+
+    fn confirm_delete(&mut self) {
+        let line = &self.lines[self.selected];
+
+        self.message = Some(if !self.options.should_allow_delete() {
+            "Deleting is disabled; start dutop with --allow-delete to enable it".to_string()
+        } else if line.depth == 0 {
+            "The root cannot be deleted".to_string()
+        } else if line.is_in_archive {
+            "What is in an archive cannot be deleted by itself".to_string()
+        } else if line.path.to_str().is_none() {
+            // The path could only be shown with replacement characters, so it could not be
+            // checked before saying yes.
+            "Paths that are not valid UTF-8 cannot be deleted".to_string()
+        } else {
+            self.pending_delete = Some(self.selected);
+            format!("Delete {} permanently? (y/N)", line.path.to_string_lossy())
+        });
+    }
+
+    fn delete(&mut self, index: usize) -> String {
+        let (last, parents) = match self.lines[index].indices.split_last() {
+            Some((&last, parents)) => (last, parents.to_vec()),
+            None => return "The root cannot be deleted".to_string(),
+        };
+        let path = self.lines[index].path.clone();
+
+        let result = if self.lines[index].is_file || self.lines[index].is_archive {
+            self.fs.remove_file(&path)
+        } else {
+            self.fs.remove_dir_all(&path)
+        };
+
+        // Even a failed removal might have deleted some of the contents of a directory, so it is
+        // scanned again in that case.
+        let message = match result {
+            Ok(()) => {
+                if let Some(parent) = entry_at(self.root.entry_mut(), &parents) {
+                    parent.remove_child(last);
+                }
+                format!("Deleted {}", path.to_string_lossy())
+            },
+            Err(error) => {
+                self.rescan(index);
+                format!("Could not delete {}: {}", path.to_string_lossy(), error)
+            },
+        };
+
+        self.refresh();
+        message
+    }
+
+    fn scroll_to_selection(&mut self, page_height: usize) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + page_height {
+            self.scroll = self.selected + 1 - page_height;
+        }
+    }
+
+    // The header, the visible lines and a status line, fitted to the width of the terminal.
+    fn render(&mut self, width: usize, height: usize) -> Vec<String> {
+        let page_height = page_height(height);
+        self.scroll_to_selection(page_height);
+
+        let format = self.options.size_format();
+        let header = format!(
+            "dutop: {} {} (sorted by {})",
+            self.root.name(),
+            self.root.size().as_size_display(format),
+            self.order.describe()
+        );
+
+        let mut screen = vec![modes::truncate(&header, width)];
+        for line in self.lines.iter().skip(self.scroll).take(page_height) {
+            let marker = match (line.is_file, line.is_expanded) {
+                (true, _) => "  ",
+                (false, true) => "▾ ",
+                (false, false) => "▸ ",
+            };
+            let text = format!(
+                "{:>size_width$} {} {}{}{}",
+                line.size.as_size_display(format),
+                modes::bar(line.size, line.parent_size, BAR_WIDTH),
+                "  ".repeat(line.depth),
+                marker,
+                line.name,
+                size_width = SIZE_WIDTH
+            );
+            screen.push(modes::truncate(&text, width));
+        }
+        while screen.len() < page_height + 1 {
+            screen.push(String::new());
+        }
+
+        let status = self.message.clone().unwrap_or(HELP.to_string());
+        screen.push(modes::truncate(&status, width));
+        screen
+    }
+
+    fn draw(&mut self, width: usize, height: usize) -> io::Result<()> {
+        let screen = self.render(width, height);
+        let selected_row = self.selected - self.scroll + 1;
+
+        let mut output = String::from("\x1b[H");
+        for (row, text) in screen.iter().enumerate() {
+            if row > 0 {
+                output.push_str("\r\n");
+            }
+            if row == selected_row {
+                // The selection is shown in reverse video across the whole width.
+                let padding = width.saturating_sub(text.chars().count());
+                output.push_str(&format!("\x1b[7m{}{}\x1b[0m", text, " ".repeat(padding)));
+            } else {
+                output.push_str(text);
+                output.push_str("\x1b[K");
+            }
+        }
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(output.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+fn entry_at<'a>(entry: &'a mut Entry, indices: &[usize]) -> Option<&'a mut Entry> {
+    match indices.split_first() {
+        None => Some(entry),
+        Some((&first, rest)) => entry.child_mut(first).and_then(|child| entry_at(child, rest)),
+    }
+}
+
+// Waits at most timeout milliseconds for something to read.
+fn has_input(timeout: i32) -> bool {
+    let mut poll = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+    unsafe { libc::poll(&mut poll, 1, timeout) > 0 }
+}
+
+fn page_height(height: usize) -> usize {
+    // One line is used for the header and another for the status line.
+    cmp::max(height.saturating_sub(2), 1)
+}
+
+// Puts the terminal in raw mode and switches to the alternate screen for as long as it is alive,
+// so the terminal is restored even when something goes wrong.
+struct RawTerminal {
+    original: libc::termios,
+}
+
+impl RawTerminal {
+    fn enable() -> Result<RawTerminal, String> {
+        let original = unsafe {
+            let mut original: libc::termios = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error().to_string());
+            }
+
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error().to_string());
+            }
+            original
+        };
+
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        io::stdout().flush().map_err(|error| error.to_string())?;
+        Ok(RawTerminal { original: original })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+pub fn browse<F: FileSystem>(fs: &F, root: Root, options: &Options) {
+    let name = root.name().clone();
+    if !terminal::is_interactive() {
+        println!("{}: Interactive mode needs a terminal", name);
+        return;
+    }
+
+    let browser = Browser::new(fs,
+                               root,
+                               options,
+                               Filter::for_options(options),
+                               options.should_look_into_archives());
+    if let Err(message) = run(browser) {
+        println!("{}: {}", name, message);
+    }
+}
+
+fn run<F: FileSystem>(mut browser: Browser<F>) -> Result<(), String> {
+    let _terminal = RawTerminal::enable()?;
+    let mut stdin = io::stdin();
+    let mut buffer = [0u8; 64];
+
+    loop {
+        // The size is checked every time so resizing the terminal works after the next key.
+        let width = terminal::width().unwrap_or(80);
+        let height = terminal::height().unwrap_or(24);
+        browser.draw(width, height).map_err(|error| error.to_string())?;
+
+        let mut count = stdin.read(&mut buffer).map_err(|error| error.to_string())?;
+        if count == 0 {
+            return Ok(());
+        }
+        while count < buffer.len() && is_cut_off(&buffer[..count]) && has_input(ESCAPE_WAIT) {
+            match stdin.read(&mut buffer[count..]).map_err(|error| error.to_string())? {
+                0 => break,
+                more => count += more,
+            }
+        }
+
+        for key in parse_keys(&buffer[..count]) {
+            if !browser.handle(key, page_height(height)) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archives::tests::tar;
+    use filesystem::{MemoryFileSystem, RealFileSystem};
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+    use utils;
+    use modes::Mode;
+    use utils::tests::{self, TempDir};
+
+    fn browser_for<'a>(path: &str, options: &'a Options) -> Browser<'a, RealFileSystem> {
+        let root = Root::for_path(Path::new(path)).unwrap();
+        Browser::new(&RealFileSystem, root, options, Filter::new(), false)
+    }
+
+    fn names<F: FileSystem>(browser: &Browser<F>) -> Vec<String> {
+        browser.lines.iter().map(|line| line.name.clone()).collect()
+    }
+
+    #[test]
+    fn it_parses_keys_and_escape_sequences() {
+        assert_eq!(
+            parse_keys(b"\x1b[A\x1b[B\x1bOC\x1b[D"),
+            vec![Key::Up, Key::Down, Key::Right, Key::Left]
+        );
+        assert_eq!(
+            parse_keys(b"\x1b[5~\x1b[6~\x1b[H\x1b[F"),
+            vec![Key::PageUp, Key::PageDown, Key::Home, Key::End]
+        );
+        assert_eq!(
+            parse_keys(b"q\r\x03\x1b"),
+            vec![Key::Char('q'), Key::Enter, Key::Interrupt, Key::Escape]
+        );
+    }
+
+    #[test]
+    fn it_knows_when_an_escape_sequence_is_cut_off() {
+        assert!(is_cut_off(b"\x1b"));
+        assert!(is_cut_off(b"q\x1b["));
+        assert!(is_cut_off(b"\x1b[A\x1bO"));
+        assert!(is_cut_off(b"\x1b[5"));
+        assert!(!is_cut_off(b""));
+        assert!(!is_cut_off(b"q"));
+        assert!(!is_cut_off(b"\x1b[A"));
+        assert!(!is_cut_off(b"\x1b[5~"));
+    }
+
+    #[test]
+    fn it_rescans_with_the_filter() {
+        let directory = TempDir::new("tui-rescan");
+        fs::create_dir_all(directory.join("logs")).unwrap();
+        fs::write(directory.join("logs/old.log"), vec![0u8; 300]).unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(100 * utils::SECONDS_PER_DAY);
+        let set_modified = |time: SystemTime| {
+            fs::File::options().write(true).open(directory.join("logs/old.log")).unwrap()
+                .set_modified(time).unwrap();
+        };
+        set_modified(long_ago);
+        let path = directory.path().to_string_lossy().into_owned();
+
+        let options = Options::builder()
+            .mode(Mode::Interactive)
+            .older_than(30 * utils::SECONDS_PER_DAY)
+            .build();
+        let filter = Filter::for_options(&options);
+        let root = filter.apply(Root::for_path(Path::new(&path)).unwrap());
+        let mut browser = Browser::new(&RealFileSystem, root, &options, filter, false);
+        assert_eq!(names(&browser), vec![format!("{}/", path), "logs/".to_string()]);
+
+        // The file does not match anymore once it has been changed.
+        set_modified(SystemTime::now());
+        browser.select(1);
+        browser.handle(Key::Char('r'), 10);
+        assert_eq!(names(&browser).len(), 1);
+    }
+
+    #[test]
+    fn it_cycles_sort_orders() {
+        assert_eq!(SortOrder::SizeDescending.next(), SortOrder::SizeAscending);
+        assert_eq!(SortOrder::SizeAscending.next(), SortOrder::Name);
+        assert_eq!(SortOrder::Name.next(), SortOrder::SizeDescending);
+    }
+
+    #[test]
+    fn it_starts_with_the_root_expanded() {
+        let options = Options::builder().mode(Mode::Interactive).build();
+        let browser = browser_for("./src", &options);
+
+        assert_eq!(browser.lines[0].name, "./src/");
+        assert_eq!(browser.lines[0].is_expanded, true);
+        assert!(browser.lines.len() > 1);
+        assert!(browser.lines.iter().skip(1).all(|line| line.depth == 1));
+
+        let sizes: Vec<u64> = browser.lines.iter().skip(1).map(|line| line.size).collect();
+        let mut sorted = sizes.clone();
+        sorted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(sizes, sorted);
+    }
+
+    #[test]
+    fn it_expands_and_collapses_directories() {
+        let options = Options::builder().mode(Mode::Interactive).build();
+        let mut browser = browser_for(".", &options);
+        let collapsed = browser.lines.len();
+
+        let src = browser.lines.iter().position(|line| line.name == "src/").unwrap();
+        browser.select(src);
+        assert!(browser.handle(Key::Right, 10));
+        assert!(browser.lines.len() > collapsed);
+        assert!(browser.lines.iter().any(|line| line.name == "main.rs" && line.depth == 2));
+
+        // Moves into the directory, then back out to it again.
+        browser.handle(Key::Right, 10);
+        assert_eq!(browser.lines[browser.selected].depth, 2);
+        browser.handle(Key::Left, 10);
+        assert_eq!(browser.selected, src);
+
+        browser.handle(Key::Enter, 10);
+        assert_eq!(browser.lines.len(), collapsed);
+        assert_eq!(browser.handle(Key::Char('q'), 10), false);
+    }
+
+    #[test]
+    fn it_changes_the_sort_order() {
+        let options = Options::builder().mode(Mode::Interactive).build();
+        let mut browser = browser_for("./src", &options);
+
+        browser.handle(Key::Char('s'), 10);
+        let sizes: Vec<u64> = browser.lines.iter().skip(1).map(|line| line.size).collect();
+        let mut sorted = sizes.clone();
+        sorted.sort();
+        assert_eq!(sizes, sorted);
+
+        browser.handle(Key::Char('s'), 10);
+        let names: Vec<String> = names(&browser).into_iter().skip(1).collect();
+        let mut sorted = names.clone();
+        sorted.sort_by_key(|name| name.to_lowercase());
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn it_renders_sizes_bars_and_markers() {
+        let options = Options::builder()
+            .mode(Mode::Interactive)
+            .size_format(tests::bytes())
+            .build();
+        let mut browser = browser_for(".", &options);
+        let screen = browser.render(200, 10);
+
+        assert_eq!(screen.len(), 10);
+        assert!(screen[0].starts_with("dutop: ./ "));
+        assert!(screen[1].ends_with("[####################] ▾ ./"));
+        assert!(screen.iter().any(|line| line.ends_with("▸ src/")));
+        assert!(screen.iter().any(|line| line.ends_with("    LICENSE")));
+        assert_eq!(screen[9], HELP);
+    }
+
+    #[test]
+    fn it_deletes_archives_but_not_what_is_in_them() {
+        let directory = TempDir::new("tui-archive");
+        fs::write(directory.join("backup.tar"), tar(&[("a.txt", b"a")])).unwrap();
+        let path = directory.path().to_string_lossy().into_owned();
+
+        let options = Options::builder()
+            .mode(Mode::Interactive)
+            .allow_delete(true)
+            .look_into_archives(true)
+            .build();
+        let root = Root::scan_path(&RealFileSystem, Path::new(&path), true).unwrap();
+        let mut browser = Browser::new(&RealFileSystem, root, &options, Filter::new(), true);
+        browser.select(1);
+        browser.handle(Key::Right, 10);
+        assert_eq!(names(&browser)[1..], ["backup.tar/".to_string(), "a.txt".to_string()]);
+
+        browser.select(2);
+        browser.handle(Key::Char('r'), 10);
+        assert!(browser.message.as_ref().unwrap().contains("only be rescanned with the archive"));
+        browser.handle(Key::Char('d'), 10);
+        assert_eq!(browser.pending_delete, None);
+
+        browser.select(1);
+        browser.handle(Key::Char('d'), 10);
+        browser.handle(Key::Char('y'), 10);
+        assert!(!directory.join("backup.tar").exists());
+        assert_eq!(browser.lines.len(), 1);
+    }
+
+    #[test]
+    fn it_only_deletes_when_allowed_and_confirmed() {
+        let directory = TempDir::new("tui-delete");
+        fs::write(directory.join("doomed"), b"goodbye").unwrap();
+        let path = directory.path().to_string_lossy().into_owned();
+
+        let disallowed = Options::builder().mode(Mode::Interactive).build();
+        let mut browser = browser_for(&path, &disallowed);
+        browser.select(1);
+        browser.handle(Key::Char('d'), 10);
+        assert_eq!(browser.pending_delete, None);
+        assert!(browser.message.as_ref().unwrap().contains("--allow-delete"));
+
+        let allowed = Options::builder().mode(Mode::Interactive).allow_delete(true).build();
+        let mut browser = browser_for(&path, &allowed);
+        browser.select(1);
+        browser.handle(Key::Char('d'), 10);
+        browser.handle(Key::Char('n'), 10);
+        assert!(directory.join("doomed").exists());
+
+        browser.handle(Key::Char('d'), 10);
+        browser.handle(Key::Char('y'), 10);
+        assert!(!directory.join("doomed").exists());
+        assert_eq!(browser.lines.len(), 1);
+    }
+
+    #[test]
+    fn it_deletes_and_rescans_in_the_file_system_it_is_given() {
+        let broken = Path::new(OsStr::from_bytes(b"project/caf\xe9"));
+        let fs = MemoryFileSystem::new()
+            .file("project/doomed", 300)
+            .file(broken.join("menu"), 200);
+        let options = Options::builder().mode(Mode::Interactive).allow_delete(true).build();
+        let root = Root::for_path_in(&fs, Path::new("project")).unwrap();
+        let mut browser = Browser::new(&fs, root, &options, Filter::new(), false);
+        assert_eq!(names(&browser), vec!["project/", "doomed", "caf\u{fffd}/"]);
+        assert_eq!(browser.lines[2].path, broken);
+
+        // The directory is found by its name on disk, but cannot be deleted since the name
+        // that would be confirmed is not the real one.
+        browser.select(2);
+        browser.handle(Key::Char('r'), 10);
+        assert_eq!(browser.message, Some("Rescanned project/caf\u{fffd}".to_string()));
+        browser.handle(Key::Char('d'), 10);
+        assert_eq!(browser.pending_delete, None);
+        assert!(browser.message.as_ref().unwrap().contains("not valid UTF-8"));
+
+        browser.select(1);
+        browser.handle(Key::Char('d'), 10);
+        browser.handle(Key::Char('y'), 10);
+        assert!(fs.metadata(Path::new("project/doomed")).is_err());
+        assert_eq!(names(&browser), vec!["project/", "caf\u{fffd}/"]);
+    }
+}
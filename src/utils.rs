@@ -1,5 +1,11 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
 use std::path::Path;
 use std::io::{Error,ErrorKind};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
 
 pub fn full_name_from_path(path: &Path, is_dir: bool) -> String {
     let name = path.to_string_lossy().into_owned();
@@ -16,6 +22,24 @@ pub fn short_name_from_path(path: &Path, is_dir: bool) -> String {
     }
 }
 
+// The name as it is on disk when it is not valid UTF-8, since the names above can only show it
+// with replacement characters.
+pub fn non_utf8_name(name: &OsStr) -> Option<OsString> {
+    match name.to_str() {
+        Some(..) => None,
+        None => Some(name.to_os_string()),
+    }
+}
+
+// The full path of a name from the root of the file system, like "/home/user/src/" for "src/".
+// Names of what cannot be found are kept as they are.
+pub fn canonical_name(name: &str) -> String {
+    match fs::canonicalize(name) {
+        Ok(path) => full_name_from_path(&path, name.ends_with('/')),
+        Err(..) => name.to_string(),
+    }
+}
+
 fn add_slash_if_needed(string: String, is_dir: bool) -> String {
     match (string.ends_with("/"), is_dir) {
         (true, true) | (false, false) => string,
@@ -38,37 +62,340 @@ pub fn describe_io_error(error: Error) -> String {
     }.to_string()
 }
 
+// Suffixes that are added on top of another extension, like in "archive.tar.gz".
+const COMPRESSION_EXTENSIONS: &'static [&'static str] = &["gz", "bz2", "xz", "zst", "lz", "z"];
+
+// Extension of a file name, without the dot and in lower case. Dotfiles without another dot in
+// them (".bashrc") have no extension. Compressed files keep the extension of what was compressed
+// ("tar.gz", "log.gz"), unless it's a number like in rotated logs ("syslog.2.gz").
+pub fn file_extension(name: &str) -> Option<String> {
+    let name = name.trim_start_matches('.').to_lowercase();
+    let last = match name.rfind('.') {
+        Some(index) if index + 1 < name.len() => index,
+        _ => return None,
+    };
+
+    if COMPRESSION_EXTENSIONS.contains(&&name[last + 1..]) {
+        if let Some(inner) = name[..last].rfind('.') {
+            let inner_extension = &name[inner + 1..last];
+            if !inner_extension.is_empty() && !inner_extension.chars().all(|c| c.is_digit(10)) {
+                return Some(name[inner + 1..].to_string());
+            }
+        }
+    }
+
+    Some(name[last + 1..].to_string())
+}
+
+pub fn escape_markup(string: &str) -> String {
+    string
+        .replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Units {
+    // Powers of 1000: kB, MB, GB, ...
+    Decimal,
+    // Powers of 1024: KiB, MiB, GiB, ...
+    Binary,
+    // The exact number of bytes.
+    Bytes,
+    // The number of blocks of the given size, rounded up like du(1) does.
+    Blocks(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigitGrouping {
+    pub separator: String,
+    pub decimal_mark: char,
+}
+
+impl DigitGrouping {
+    pub fn with_separator(separator: &str) -> DigitGrouping {
+        DigitGrouping {
+            separator: separator.to_string(),
+            // "1.234.567.00" would be impossible to read, so switch to a decimal comma like the
+            // locales that group with dots do.
+            decimal_mark: if separator == "." { ',' } else { '.' },
+        }
+    }
+
+    // Reads the numeric locale from the environment in the same order as setlocale(3) does.
+    pub fn from_env() -> DigitGrouping {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"].iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+
+        DigitGrouping::for_locale(&locale)
+    }
+
+    pub fn for_locale(locale: &str) -> DigitGrouping {
+        // Only the language matters here; "de_CH.UTF-8" and "de" are both German.
+        let language = locale.split(|c| c == '_' || c == '.' || c == '@').next().unwrap_or("");
+
+        let (separator, decimal_mark) = match language {
+            "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl" |
+            "sr" | "is" | "vi" => (".", ','),
+            "fr" | "sv" | "fi" | "nb" | "nn" | "no" | "ru" | "pl" | "cs" | "sk" | "uk" | "hu" |
+            "bg" | "et" | "lv" | "lt" => (" ", ','),
+            // English and the "C" locale, which has no separator of its own.
+            _ => (",", '.'),
+        };
+
+        DigitGrouping {
+            separator: separator.to_string(),
+            decimal_mark: decimal_mark,
+        }
+    }
+
+    fn apply(&self, number: &str) -> String {
+        let (integer, fraction) = match number.find('.') {
+            Some(index) => (&number[..index], Some(&number[index + 1..])),
+            None => (number, None),
+        };
+
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push_str(&self.separator);
+            }
+            grouped.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            grouped.push(self.decimal_mark);
+            grouped.push_str(fraction);
+        }
+
+        grouped
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeFormat {
+    pub units: Units,
+    pub precision: usize,
+    pub grouping: Option<DigitGrouping>,
+}
+
+impl Default for SizeFormat {
+    fn default() -> SizeFormat {
+        SizeFormat {
+            units: Units::Decimal,
+            precision: 2,
+            grouping: None,
+        }
+    }
+}
+
 pub trait SizeDisplay {
-    fn as_size_display(&self) -> String;
+    fn as_size_display(&self, format: &SizeFormat) -> String;
 }
 
-const KILO: f64 = 1e3 as f64;
-const MEGA: f64 = 1e6 as f64;
-const GIGA: f64 = 1e9 as f64;
+const DECIMAL_UNITS: [&'static str; 6] = ["kB", "MB", "GB", "TB", "PB", "EB"];
+const BINARY_UNITS: [&'static str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
 
-const KILO_CUTOFF: u64 = (0.6 * KILO) as u64;
-const MEGA_CUTOFF: u64 = (1.4 * MEGA) as u64;
-const GIGA_CUTOFF: u64 = (1.4 * GIGA) as u64;
+// Sizes are shown in kilo from 0.6 of a kilo, and in every larger unit once they are above 1.4 of
+// it. This keeps the numbers in the output from getting too small or too large.
+const LOWER_CUTOFF: f64 = 0.6;
+const UPPER_CUTOFF: f64 = 1.4;
 
 impl SizeDisplay for u64 {
-    fn as_size_display(&self) -> String {
-        if *self < KILO_CUTOFF {
-            return format!("{} B", *self)
-        }
+    fn as_size_display(&self, format: &SizeFormat) -> String {
+        let (number, unit) = match format.units {
+            Units::Decimal => scaled_size(*self, 1000.0, &DECIMAL_UNITS, format.precision),
+            Units::Binary => scaled_size(*self, 1024.0, &BINARY_UNITS, format.precision),
+            Units::Bytes => (self.to_string(), Some("B")),
+            Units::Blocks(block_size) => {
+                let blocks = *self / block_size + if *self % block_size > 0 { 1 } else { 0 };
+                (blocks.to_string(), None)
+            },
+        };
 
-        let (scaled, unit) = match *self {
-            KILO_CUTOFF...MEGA_CUTOFF => (*self as f64 / KILO, "kB"),
-            MEGA_CUTOFF...GIGA_CUTOFF => (*self as f64 / MEGA, "MB"),
-            _ => (*self as f64 / GIGA, "GB"),
+        let number = match format.grouping {
+            Some(ref grouping) => grouping.apply(&number),
+            None => number,
         };
-        format!("{:.2} {}", scaled, unit)
+
+        match unit {
+            Some(unit) => format!("{} {}", number, unit),
+            None => number,
+        }
+    }
+}
+
+fn scaled_size(size: u64,
+               base: f64,
+               units: &[&'static str],
+               precision: usize) -> (String, Option<&'static str>) {
+    let bytes = size as f64;
+    if bytes < LOWER_CUTOFF * base {
+        return (size.to_string(), Some("B"));
+    }
+
+    let mut unit = base;
+    let mut index = 0;
+    while index + 1 < units.len() && bytes > UPPER_CUTOFF * unit * base {
+        unit *= base;
+        index += 1;
+    }
+
+    (format!("{:.*}", precision, bytes / unit), Some(units[index]))
+}
+
+// Parses block sizes the way du(1) does. Unlike other sizes they cannot be zero.
+pub fn parse_block_size(string: &str) -> Result<u64, String> {
+    match parse_size(string) {
+        Ok(0) | Err(_) => Err(format!("Invalid block size \"{}\"", string)),
+        Ok(size) => Ok(size),
+    }
+}
+
+// Parses sizes the way du(1) parses block sizes: an optional number followed by an optional unit.
+// "K", "M", "G" and so on (and "KiB", "MiB", ...) are powers of 1024, while "KB", "MB" and so on
+// are powers of 1000.
+pub fn parse_size(string: &str) -> Result<u64, String> {
+    let error = || format!("Invalid size \"{}\"", string);
+    let split = string.find(|c: char| !c.is_digit(10)).unwrap_or(string.len());
+    let (number, unit) = string.split_at(split);
+
+    let number = if number.is_empty() {
+        1
+    } else {
+        number.parse::<u64>().map_err(|_| error())?
+    };
+
+    let multiplier: u64 = match unit {
+        "" | "B" => 1,
+        "K" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "T" | "TiB" => 1 << 40,
+        "P" | "PiB" => 1 << 50,
+        "E" | "EiB" => 1 << 60,
+        "KB" | "kB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        "PB" => 1_000_000_000_000_000,
+        "EB" => 1_000_000_000_000_000_000,
+        _ => return Err(error()),
+    };
+
+    number.checked_mul(multiplier).ok_or_else(error)
+}
+
+// Parses ages like "30d": a number followed by an optional unit, which is one of "s", "m", "h",
+// "d" (the default), "w" or "y". The age is returned in seconds.
+pub fn parse_age(string: &str) -> Result<u64, String> {
+    let error = || format!("Invalid age \"{}\"", string);
+    let split = string.find(|c: char| !c.is_digit(10)).unwrap_or(string.len());
+    let (number, unit) = string.split_at(split);
+
+    let number = number.parse::<u64>().map_err(|_| error())?;
+    let multiplier: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "" | "d" => SECONDS_PER_DAY,
+        "w" => 7 * SECONDS_PER_DAY,
+        "y" => 365 * SECONDS_PER_DAY,
+        _ => return Err(error()),
+    };
+
+    number.checked_mul(multiplier).ok_or_else(error)
+}
+
+// Parses an interval like an age, but a plain number is a number of seconds. It cannot be zero.
+pub fn parse_interval(string: &str) -> Result<u64, String> {
+    let error = || format!("Invalid interval \"{}\"", string);
+    let seconds = match string.parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => parse_age(string).map_err(|_| error())?,
+    };
+
+    if seconds == 0 { Err(error()) } else { Ok(seconds) }
+}
+
+// Parses a list of ages in days, like "30,90,365". They have to be in increasing order.
+pub fn parse_age_buckets(string: &str) -> Result<Vec<u64>, String> {
+    let error = || format!("Invalid age buckets \"{}\"", string);
+    let days = string.split(',')
+        .map(|days| days.trim().parse::<u64>().map_err(|_| error()))
+        .collect::<Result<Vec<u64>, String>>()?;
+
+    if days.is_empty() || days[0] == 0 || days.windows(2).any(|pair| pair[0] >= pair[1]) {
+        Err(error())
+    } else {
+        Ok(days)
+    }
+}
+
+// Describes a number of seconds in the largest unit that fits, like "3 days".
+pub fn describe_duration(seconds: u64) -> String {
+    let units = [
+        (SECONDS_PER_DAY, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ];
+
+    let &(length, unit) = units.iter()
+        .find(|&&(length, _)| seconds >= length)
+        .unwrap_or(&units[3]);
+    let count = seconds / length;
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+// Seconds since the Unix epoch, like the timestamps of files.
+pub fn now() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(_) => 0,
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use super::*;
-    use std::path::Path;
+    use std::path::PathBuf;
+    use std::process;
+
+    // A directory of its own for a test, removed again when the test is done, even if it fails.
+    pub struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        pub fn new(name: &str) -> TempDir {
+            let path = env::temp_dir().join(format!("dutop-{}-{}", name, process::id()));
+            fs::create_dir_all(&path).unwrap();
+            TempDir { path: path }
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+            self.path.join(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    // Sizes in bytes, so they can be compared exactly.
+    pub fn bytes() -> SizeFormat {
+        SizeFormat { units: Units::Bytes, precision: 2, grouping: None }
+    }
 
     #[test]
     fn it_can_convert_a_path_to_a_string() {
@@ -99,17 +426,192 @@ mod tests {
         assert_eq!(full_name_from_path(&path, true), "/path/to/");
     }
 
+    #[test]
+    fn it_finds_file_extensions() {
+        assert_eq!(file_extension("movie.MP4"), Some("mp4".to_string()));
+        assert_eq!(file_extension("archive.tar.gz"), Some("tar.gz".to_string()));
+        assert_eq!(file_extension("ACCESS.LOG.GZ"), Some("log.gz".to_string()));
+        assert_eq!(file_extension("syslog.2.gz"), Some("gz".to_string()));
+        assert_eq!(file_extension("notes.gz"), Some("gz".to_string()));
+        assert_eq!(file_extension("data.parquet"), Some("parquet".to_string()));
+        assert_eq!(file_extension("Makefile"), None);
+        assert_eq!(file_extension(".bashrc"), None);
+        assert_eq!(file_extension(".config.toml"), Some("toml".to_string()));
+        assert_eq!(file_extension("trailing."), None);
+    }
+
+    #[test]
+    fn it_escapes_markup() {
+        assert_eq!(escape_markup("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    fn format(units: Units, precision: usize) -> SizeFormat {
+        SizeFormat { units: units, precision: precision, grouping: None }
+    }
+
     #[test]
     fn it_can_format_sizes() {
-        assert_eq!(          1.as_size_display(),       "1 B");
-        assert_eq!(        345.as_size_display(),     "345 B");
-        assert_eq!(       1000.as_size_display(),   "1.00 kB");
-        assert_eq!(       1100.as_size_display(),   "1.10 kB");
-        assert_eq!(      11000.as_size_display(),  "11.00 kB");
-        assert_eq!(123_456_789.as_size_display(), "123.46 MB");
-        assert_eq!(123_452_000.as_size_display(), "123.45 MB");
-
-        assert_eq!(    867_000_000_000.as_size_display(),    "867.00 GB");
-        assert_eq!(867_000_000_000_000.as_size_display(), "867000.00 GB");
+        let format = SizeFormat::default();
+
+        assert_eq!(          1.as_size_display(&format),       "1 B");
+        assert_eq!(        345.as_size_display(&format),     "345 B");
+        assert_eq!(       1000.as_size_display(&format),   "1.00 kB");
+        assert_eq!(       1100.as_size_display(&format),   "1.10 kB");
+        assert_eq!(      11000.as_size_display(&format),  "11.00 kB");
+        assert_eq!(123_456_789.as_size_display(&format), "123.46 MB");
+        assert_eq!(123_452_000.as_size_display(&format), "123.45 MB");
+
+        assert_eq!(        867_000_000_000.as_size_display(&format), "867.00 GB");
+        assert_eq!(    867_000_000_000_000.as_size_display(&format), "867.00 TB");
+        assert_eq!(867_000_000_000_000_000.as_size_display(&format), "867.00 PB");
+        assert_eq!(          ::std::u64::MAX.as_size_display(&format),  "18.45 EB");
+    }
+
+    #[test]
+    fn it_keeps_using_the_smaller_unit_until_the_cutoff() {
+        let format = SizeFormat::default();
+
+        assert_eq!(          599.as_size_display(&format),       "599 B");
+        assert_eq!(          600.as_size_display(&format),     "0.60 kB");
+        assert_eq!(    1_400_000.as_size_display(&format), "1400.00 kB");
+        assert_eq!(    1_400_001.as_size_display(&format),     "1.40 MB");
+        assert_eq!(1_400_000_001.as_size_display(&format),     "1.40 GB");
+    }
+
+    #[test]
+    fn it_can_format_sizes_in_binary_units() {
+        let format = format(Units::Binary, 2);
+
+        assert_eq!(          614.as_size_display(&format),      "614 B");
+        assert_eq!(         1024.as_size_display(&format),  "1.00 KiB");
+        assert_eq!(    1_572_864.as_size_display(&format),  "1.50 MiB");
+        assert_eq!(1_099_511_627_776.as_size_display(&format), "1024.00 GiB");
+        assert_eq!(2_199_023_255_552.as_size_display(&format), "2.00 TiB");
+    }
+
+    #[test]
+    fn it_can_format_exact_bytes() {
+        let format = format(Units::Bytes, 2);
+        assert_eq!(123_456_789.as_size_display(&format), "123456789 B");
+    }
+
+    #[test]
+    fn it_can_format_blocks() {
+        let format = format(Units::Blocks(1024), 2);
+
+        assert_eq!(   0.as_size_display(&format), "0");
+        assert_eq!(   1.as_size_display(&format), "1");
+        assert_eq!(1024.as_size_display(&format), "1");
+        assert_eq!(1025.as_size_display(&format), "2");
+    }
+
+    #[test]
+    fn it_can_change_precision() {
+        assert_eq!(123_456_789.as_size_display(&format(Units::Decimal, 0)), "123 MB");
+        assert_eq!(123_456_789.as_size_display(&format(Units::Decimal, 4)), "123.4568 MB");
+        assert_eq!(        345.as_size_display(&format(Units::Decimal, 4)), "345 B");
+    }
+
+    #[test]
+    fn it_can_group_digits() {
+        let mut format = format(Units::Bytes, 2);
+        format.grouping = Some(DigitGrouping::with_separator(","));
+
+        assert_eq!(            123.as_size_display(&format),             "123 B");
+        assert_eq!(          1_234.as_size_display(&format),           "1,234 B");
+        assert_eq!(    123_456_789.as_size_display(&format),     "123,456,789 B");
+        assert_eq!(123_456_789_012.as_size_display(&format), "123,456,789,012 B");
+
+        format.grouping = Some(DigitGrouping::with_separator(" "));
+        assert_eq!(123_456_789_012.as_size_display(&format), "123 456 789 012 B");
+    }
+
+    #[test]
+    fn it_groups_the_integer_part_of_scaled_sizes() {
+        let mut format = format(Units::Decimal, 2);
+        format.grouping = Some(DigitGrouping::with_separator(","));
+        assert_eq!(1_400_000.as_size_display(&format), "1,400.00 kB");
+
+        format.grouping = Some(DigitGrouping::with_separator("."));
+        assert_eq!(1_400_000.as_size_display(&format), "1.400,00 kB");
+    }
+
+    #[test]
+    fn it_groups_blocks() {
+        let mut format = format(Units::Blocks(1), 2);
+        format.grouping = Some(DigitGrouping::with_separator("'"));
+        assert_eq!(1_234_567.as_size_display(&format), "1'234'567");
+    }
+
+    #[test]
+    fn it_picks_digit_grouping_from_the_locale() {
+        assert_eq!(DigitGrouping::for_locale("en_US.UTF-8"), DigitGrouping::with_separator(","));
+        assert_eq!(DigitGrouping::for_locale("C"), DigitGrouping::with_separator(","));
+        assert_eq!(DigitGrouping::for_locale(""), DigitGrouping::with_separator(","));
+        assert_eq!(DigitGrouping::for_locale("de_DE.UTF-8"), DigitGrouping::with_separator("."));
+
+        let swedish = DigitGrouping::for_locale("sv_SE.UTF-8");
+        assert_eq!(swedish.separator, " ");
+        assert_eq!(swedish.decimal_mark, ',');
+    }
+
+    #[test]
+    fn it_parses_block_sizes() {
+        assert_eq!(parse_block_size("512"), Ok(512));
+        assert_eq!(parse_block_size("K"), Ok(1024));
+        assert_eq!(parse_block_size("4K"), Ok(4096));
+        assert_eq!(parse_block_size("1MiB"), Ok(1_048_576));
+        assert_eq!(parse_block_size("1MB"), Ok(1_000_000));
+        assert_eq!(parse_block_size("2G"), Ok(2_147_483_648));
+        assert!(parse_block_size("0").is_err());
+        assert!(parse_block_size("12X").is_err());
+        assert!(parse_block_size("-1").is_err());
+        assert!(parse_block_size("99999E").is_err());
+    }
+
+    #[test]
+    fn it_parses_ages() {
+        assert_eq!(parse_age("30"), Ok(30 * SECONDS_PER_DAY));
+        assert_eq!(parse_age("30d"), Ok(30 * SECONDS_PER_DAY));
+        assert_eq!(parse_age("2w"), Ok(14 * SECONDS_PER_DAY));
+        assert_eq!(parse_age("1y"), Ok(365 * SECONDS_PER_DAY));
+        assert_eq!(parse_age("12h"), Ok(12 * 60 * 60));
+        assert_eq!(parse_age("d"), Err("Invalid age \"d\"".to_string()));
+        assert!(parse_age("3x").is_err());
+    }
+
+    #[test]
+    fn it_parses_intervals() {
+        assert_eq!(parse_interval("5"), Ok(5));
+        assert_eq!(parse_interval("2m"), Ok(120));
+        assert_eq!(parse_interval("1d"), Ok(SECONDS_PER_DAY));
+        assert_eq!(parse_interval("0"), Err("Invalid interval \"0\"".to_string()));
+        assert!(parse_interval("5x").is_err());
+    }
+
+    #[test]
+    fn it_describes_durations() {
+        assert_eq!(describe_duration(0), "0 seconds");
+        assert_eq!(describe_duration(1), "1 second");
+        assert_eq!(describe_duration(150), "2 minutes");
+        assert_eq!(describe_duration(3 * 60 * 60), "3 hours");
+        assert_eq!(describe_duration(SECONDS_PER_DAY + 1), "1 day");
+    }
+
+    #[test]
+    fn it_parses_age_buckets() {
+        assert_eq!(parse_age_buckets("30,90,365"), Ok(vec![30, 90, 365]));
+        assert_eq!(parse_age_buckets("7"), Ok(vec![7]));
+        assert!(parse_age_buckets("90,30").is_err());
+        assert!(parse_age_buckets("0,30").is_err());
+        assert!(parse_age_buckets("30,,90").is_err());
+    }
+
+    #[test]
+    fn it_parses_sizes() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("10M"), Ok(10_485_760));
+        assert_eq!(parse_size("10MB"), Ok(10_000_000));
+        assert_eq!(parse_size("10X"), Err("Invalid size \"10X\"".to_string()));
     }
 }
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use entry::Entry;
+use modes::DisplayableEntry;
+
+// Goes through an entry and everything below it, every directory before what is in it and the
+// children of a directory in the order they are sorted in. Paths are relative to the entry the walk
+// started at, which has an empty path itself.
+#[derive(Debug)]
+pub struct Walk<'a> {
+    pending: Vec<(PathBuf, &'a Entry)>,
+}
+
+impl<'a> Walk<'a> {
+    pub fn new(entry: &'a Entry) -> Walk<'a> {
+        Walk { pending: vec![(PathBuf::new(), entry)] }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (PathBuf, &'a Entry);
+
+    fn next(&mut self) -> Option<(PathBuf, &'a Entry)> {
+        let (path, entry) = self.pending.pop()?;
+
+        // Pushed in reverse, so the first child is the next one to come out.
+        for child in entry.children_iter().rev() {
+            self.pending.push((path.join(child.name().trim_end_matches('/')), child));
+        }
+        Some((path, entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use root::Root;
+    use std::path::Path;
+
+    #[test]
+    fn it_walks_parents_before_children() {
+        let root = Root::for_path(Path::new("./src")).unwrap();
+        let paths: Vec<PathBuf> = root.walk().map(|(path, _)| path).collect();
+
+        assert_eq!(paths[0], PathBuf::new());
+        assert_eq!(paths.len(), root.children_iter().count() + 1);
+        assert!(paths.contains(&PathBuf::from("walk.rs")));
+
+        let sizes: Vec<u64> = root.walk().skip(1).map(|(_, entry)| entry.size()).collect();
+        let mut sorted = sizes.clone();
+        sorted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(sizes, sorted);
+    }
+}
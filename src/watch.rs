@@ -0,0 +1,139 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use options::Options;
+use diff;
+use entry::Entry;
+use modes::{self, DisplayableEntry};
+use root::Root;
+use terminal;
+use utils::{self, SizeDisplay};
+
+// Scans the roots again and again, redrawing the tree every time. Every entry is annotated with
+// how much it changed since the scan before, and how fast. Never returns, since it only stops
+// when interrupted.
+pub fn run<F>(interval: Duration, options: &Options, mut scan: F) -> !
+    where F: FnMut() -> Vec<Root> {
+    let mut previous: Option<(Instant, Vec<Root>)> = None;
+
+    loop {
+        let roots = scan();
+        let scanned = Instant::now();
+
+        let every = utils::describe_duration(interval.as_secs());
+        let mut lines = vec![format!("Every {}, press Ctrl-C to stop", every)];
+        for root in roots.iter() {
+            let (old_root, elapsed) = match previous {
+                Some((time, ref old_roots)) => (
+                    old_roots.iter().find(|old_root| old_root.name() == root.name()),
+                    scanned.duration_since(time),
+                ),
+                None => (None, Duration::from_secs(0)),
+            };
+
+            let old_entry = old_root.map(|old_root| old_root.entry());
+            add_lines(old_entry, root.entry(), root.name(), elapsed, options, 0, "", &mut lines);
+        }
+
+        // Clear the screen and start from the top, leaving out what does not fit.
+        let height = terminal::height().unwrap_or(usize::max_value());
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let _ = write!(out, "\x1b[H\x1b[2J");
+        for line in lines.iter().take(height.saturating_sub(1).max(1)) {
+            let _ = writeln!(out, "{}", line);
+        }
+        let _ = out.flush();
+
+        previous = Some((scanned, roots));
+        thread::sleep(interval);
+    }
+}
+
+// Entries are matched with the entries of the previous scan by name, like in --diff. Nothing is
+// annotated on the first scan, since there is nothing to compare with yet.
+fn add_lines(old: Option<&Entry>,
+             new: &Entry,
+             name: &str,
+             elapsed: Duration,
+             options: &Options,
+             level: usize,
+             prefix: &str,
+             lines: &mut Vec<String>) {
+    let size = new.size().as_size_display(options.size_format());
+    let mut line = format!("{}{} {}", prefix, name, size);
+    if elapsed > Duration::from_secs(0) {
+        line.push_str(&annotation(old, new, elapsed, options));
+    }
+    lines.push(line);
+
+    if options.depth_accepts(level) {
+        for child in modes::visible_children(new, options) {
+            let old_child = old.and_then(|old| {
+                old.children_iter().find(|old_child| old_child.name() == child.name())
+            });
+            add_lines(old_child, child, child.name(), elapsed, options, level + 1,
+                      &format!("{}  ", prefix), lines);
+        }
+    }
+}
+
+fn annotation(old: Option<&Entry>, new: &Entry, elapsed: Duration, options: &Options) -> String {
+    let old = match old {
+        Some(old) => old,
+        None => return " (new)".to_string(),
+    };
+
+    let difference = new.size() as i64 - old.size() as i64;
+    if difference == 0 {
+        return String::new();
+    }
+
+    let rate = difference as f64 / elapsed.as_secs_f64();
+    format!(" ({}, {}/s)",
+            diff::signed_size(difference, options),
+            diff::signed_size(rate.round() as i64, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use options::{Depth, Limit};
+    use std::fs;
+    use utils::tests::{self, TempDir};
+
+    #[test]
+    fn it_annotates_changes_and_growth_rates() {
+        let base = TempDir::new("watch");
+        fs::create_dir_all(base.join("logs")).unwrap();
+        fs::write(base.join("logs/app.log"), vec![0u8; 1000]).unwrap();
+        fs::write(base.join("same"), vec![0u8; 500]).unwrap();
+        let old = Root::for_path(base.path()).unwrap();
+
+        fs::write(base.join("logs/app.log"), vec![0u8; 5000]).unwrap();
+        fs::write(base.join("logs/new.log"), vec![0u8; 100]).unwrap();
+        let new = Root::for_path(base.path()).unwrap();
+
+        let options = Options::builder()
+            .size_format(tests::bytes())
+            .depth(Depth::Limited(2))
+            .limit(Limit::Unlimited)
+            .build();
+        let mut lines = Vec::new();
+        add_lines(Some(old.entry()), new.entry(), "root/", Duration::from_secs(2), &options, 0, "",
+                  &mut lines);
+
+        // The size of the directory itself may change as well, depending on the file system.
+        assert!(lines[1].starts_with("  logs/ "));
+        assert_eq!(&lines[2..], &[
+            "    app.log 5000 B (+4000 B, +2000 B/s)",
+            "    new.log 100 B (new)",
+            "  same 500 B",
+        ]);
+
+        let mut lines = Vec::new();
+        add_lines(None, new.entry(), "root/", Duration::from_secs(0), &options, 0, "", &mut lines);
+        assert_eq!(lines[2], "    app.log 5000 B");
+    }
+}